@@ -1,9 +1,27 @@
 use std::vec;
 
 use crate::physics::body::*;
+use crate::physics::broadphase::*;
+use crate::physics::clipping::split_polygon;
+use crate::physics::collision::ray_cast;
+use crate::physics::polygon::Polygon;
+use crate::physics::world::World;
+use crate::render::renderer::Renderer;
 use crate::render::shape::*;
 use macroquad::prelude::*;
-use macroquad::rand::gen_range as random_range;
+use macroquad::rand::RandGenerator;
+
+const ASTEROID_RESTITUTION: f32 = 0.9;
+// fracture pieces with less area crumble to dust
+const MIN_FRAGMENT_AREA: f32 = 200.0;
+// pixels per second pushing fracture pieces away from the cut and the hit
+const FRACTURE_SPEED: f32 = 40.0;
+const HIT_SPEED: f32 = 20.0;
+// random positions tried before giving up on the spawn rules' clearance
+const SPAWN_ATTEMPTS: usize = 32;
+// share of a step an outline vertex's angle moves either way, less than half
+// so neighbours can't swap order
+const ANGLE_JITTER: f32 = 0.4;
 
 #[derive(Clone)]
 pub enum AsteroidSizes {
@@ -20,6 +38,44 @@ fn get_asteroid_scale(size: &AsteroidSizes) -> f32 {
     }
 }
 
+// outlines of one size have about the same area, so mass grows with the scale squared
+fn get_asteroid_mass(size: &AsteroidSizes) -> f32 {
+    let scale = get_asteroid_scale(size);
+    scale * scale
+}
+
+// largest spin in radians per second, small rocks tumble faster
+fn get_asteroid_spin(size: &AsteroidSizes) -> f32 {
+    match size {
+        AsteroidSizes::Small => 2.0,
+        AsteroidSizes::Medium => 1.2,
+        AsteroidSizes::Large => 0.6,
+    }
+}
+
+// vertex count and roughness of generated outlines, roughness is the largest
+// share of the radius a vertex can be pulled in by
+fn get_asteroid_outline(size: &AsteroidSizes) -> (usize, f32) {
+    match size {
+        AsteroidSizes::Small => (8, 0.25),
+        AsteroidSizes::Medium => (10, 0.3),
+        AsteroidSizes::Large => (12, 0.35),
+    }
+}
+
+// Average area of the outlines `generate_asteroid_outline` makes: a fan of
+// triangles around the center, each spanning two vertices. Radii and the angles
+// between neighbours vary independently, so their averages multiply.
+fn get_asteroid_area(size: &AsteroidSizes) -> f32 {
+    let (vertex_count, roughness) = get_asteroid_outline(size);
+    let angle_step = 2.0 * std::f32::consts::PI / vertex_count as f32;
+    let mean_radius = 3.0 * get_asteroid_scale(size) * (1.0 - roughness / 2.0);
+    // the average sine of a step widened by the difference of two uniform jitters
+    let jitter = ANGLE_JITTER * angle_step;
+    let mean_sine = angle_step.sin() * (jitter.sin() / jitter).powi(2);
+    vertex_count as f32 / 2.0 * mean_radius * mean_radius * mean_sine
+}
+
 fn get_asteroid_velocity(size: &AsteroidSizes) -> f32 {
     match size {
         AsteroidSizes::Small => 100.0,
@@ -43,63 +99,199 @@ pub struct Asteroid {
 }
 
 impl Asteroid {
-    pub fn new(start_point: Vec2, rotation: f32, size: &AsteroidSizes) -> Asteroid {
-        let scale = get_asteroid_scale(&size);
-        let points = vec![
-            vec2(-3.0, -2.0) * scale,
-            vec2(-1.26, -2.76) * scale,
-            vec2(-0.28, -1.88) * scale,
-            vec2(2.28, -2.48) * scale,
-            vec2(3.66, 0.0) * scale,
-            vec2(0.44, 1.24) * scale,
-            vec2(1.78, 1.56) * scale,
-            vec2(0.58, 2.9) * scale,
-            vec2(-1.18, 2.22) * scale,
-            vec2(-2.26, 3.04) * scale,
-            vec2(-3.54, 1.66) * scale,
-            vec2(-2.68, 0.34) * scale,
-            vec2(-3.0, -2.0) * scale,
-        ];
-
-        let velocity = get_asteroid_velocity(&size);
-        Asteroid {
+    // `heading` is the direction of travel, `spin` how fast the outline turns as a share
+    // of the size's largest spin, -1..1. `outline` is centered on the origin, see
+    // `generate_asteroid_outline`.
+    pub fn new(
+        start_point: Vec2,
+        heading: f32,
+        spin: f32,
+        size: &AsteroidSizes,
+        outline: Polygon,
+    ) -> Asteroid {
+        let scale = get_asteroid_scale(size);
+        let velocity = get_asteroid_velocity(size);
+        let mass = get_asteroid_mass(size);
+        let mut asteroid = Asteroid {
             body: Body {
-                rotation,
-                point: start_point,
-                velocity: vec2(velocity * rotation.cos(), velocity * rotation.sin()),
-                acceleration: vec2(0.0, 0.0),
-                drag_coefficient: 1.0,
-                screen_edge_behavior: ScreenEdgeBehavior::Wrap,
-                destroyed: false,
-            },
-            shape: Shape {
-                points,
-                color: WHITE,
-                thickness: 2.0,
+                mass,
+                // solid disc, close enough for a lumpy rock
+                moment_of_inertia: 0.5 * mass * (3.0 * scale) * (3.0 * scale),
+                angular_velocity: spin * get_asteroid_spin(size),
+                restitution: ASTEROID_RESTITUTION,
+                ..Body::new(
+                    start_point,
+                    heading,
+                    vec2(velocity * heading.cos(), velocity * heading.sin()),
+                    ScreenEdgeBehavior::Wrap,
+                )
             },
+            shape: Shape::new(outline, WHITE, 2.0),
             size: size.clone(),
+        };
+        asteroid.body.extent = asteroid.shape.bounding_radius();
+        asteroid.update_transforms();
+        asteroid
+    }
+
+    pub fn update_transforms(&mut self) {
+        self.shape.update_from_body(&self.body);
+    }
+
+    pub fn bounding_circle(&self) -> BoundingCircle {
+        BoundingCircle {
+            center: self.body.point,
+            radius: self.shape.bounding_radius(),
+        }
+    }
+
+    pub fn render(&self, renderer: &mut dyn Renderer, alpha: f32, world: &World) {
+        self.shape.render(renderer, &self.body, alpha, world);
+    }
+}
+
+// The outline traced from media/asteroid_points_ref, used if generating one fails.
+pub fn reference_asteroid_outline(size: &AsteroidSizes) -> Polygon {
+    let scale = get_asteroid_scale(size);
+    let points = vec![
+        vec2(-3.0, -2.0) * scale,
+        vec2(-1.26, -2.76) * scale,
+        vec2(-0.28, -1.88) * scale,
+        vec2(2.28, -2.48) * scale,
+        vec2(3.66, 0.0) * scale,
+        vec2(0.44, 1.24) * scale,
+        vec2(1.78, 1.56) * scale,
+        vec2(0.58, 2.9) * scale,
+        vec2(-1.18, 2.22) * scale,
+        vec2(-2.26, 3.04) * scale,
+        vec2(-3.54, 1.66) * scale,
+        vec2(-2.68, 0.34) * scale,
+    ];
+    Polygon::closed(points).expect("asteroid outline is a valid polygon")
+}
+
+// Jagged outline: vertices go once around the center at jittered angles and
+// radii. Angles stay in order, so the outline is closed and never crosses itself,
+// and low roughness keeps it mostly convex.
+pub fn generate_asteroid_outline(size: &AsteroidSizes, rng: &RandGenerator) -> Polygon {
+    let radius = 3.0 * get_asteroid_scale(size);
+    let (vertex_count, roughness) = get_asteroid_outline(size);
+    let angle_step = 2.0 * std::f32::consts::PI / vertex_count as f32;
+    let points = (0..vertex_count)
+        .map(|i| {
+            // less than half a step either way, so neighbours can't swap order
+            let angle = (i as f32 + rng.gen_range(-ANGLE_JITTER, ANGLE_JITTER)) * angle_step;
+            let vertex_radius = radius * (1.0 - rng.gen_range(0.0, roughness));
+            Vec2::from_angle(angle) * vertex_radius
+        })
+        .collect();
+    outline_or_reference(points, size)
+}
+
+// The outline through `points`, unless it's broken or crosses itself.
+fn outline_or_reference(points: Vec<Vec2>, size: &AsteroidSizes) -> Polygon {
+    match Polygon::closed(points) {
+        Ok(outline) if outline.is_simple() => outline,
+        _ => reference_asteroid_outline(size),
+    }
+}
+
+// Where a level's asteroids may appear relative to the point the ship spawns at.
+#[derive(Debug, Clone)]
+pub struct SpawnRules {
+    // no asteroid's outline comes closer to the spawn point than this
+    pub clearance: f32,
+    // chance from 0 to 1 of appearing within `edge_margin` of a screen edge
+    // rather than anywhere
+    pub edge_preference: f32,
+    pub edge_margin: f32,
+    // smallest angle in radians between an asteroid's heading and the
+    // direction to the spawn point, so none start on a collision course
+    pub min_miss_angle: f32,
+}
+
+impl SpawnRules {
+    // Early levels keep asteroids at the edges and aimed well off the ship,
+    // later ones allow them closer in and on tighter lines.
+    pub fn for_level(level: u32) -> SpawnRules {
+        let progress = (level.saturating_sub(1) as f32 / 10.0).min(1.0);
+        SpawnRules {
+            clearance: 150.0,
+            edge_preference: 1.0 - 0.5 * progress,
+            edge_margin: 60.0,
+            min_miss_angle: 0.5 - 0.25 * progress,
+        }
+    }
+
+    // Candidate positions are drawn until one keeps clear of `spawn_point`,
+    // falling back to the point furthest from it.
+    fn pick_point(
+        &self,
+        spawn_point: Vec2,
+        radius: f32,
+        world: &World,
+        rng: &RandGenerator,
+    ) -> Vec2 {
+        for _ in 0..SPAWN_ATTEMPTS {
+            let mut point = vec2(
+                rng.gen_range(0.0, world.width),
+                rng.gen_range(0.0, world.height),
+            );
+            if rng.gen_range(0.0, 1.0) < self.edge_preference {
+                let margin = self.edge_margin;
+                // push it into the band along one of the four edges
+                match rng.gen_range(0, 4) {
+                    0 => point.x = rng.gen_range(0.0, margin),
+                    1 => point.x = world.width - rng.gen_range(0.0, margin),
+                    2 => point.y = rng.gen_range(0.0, margin),
+                    _ => point.y = world.height - rng.gen_range(0.0, margin),
+                }
+            }
+            if world.wrapped_delta(spawn_point, point).length() >= self.clearance + radius {
+                return point;
+            }
         }
+        // opposite the spawn point on every wrapped axis
+        let furthest = spawn_point + world.center();
+        vec2(furthest.x % world.width, furthest.y % world.height)
     }
 
-    pub fn render(&self) {
-        self.shape.draw(self.body.point, self.body.rotation);
+    // `heading`, turned aside if it points too close to `spawn_point`.
+    fn steer_clear(&self, point: Vec2, heading: f32, spawn_point: Vec2, world: &World) -> f32 {
+        let towards = world.wrapped_delta(point, spawn_point);
+        if towards == Vec2::ZERO {
+            return heading;
+        }
+        let miss_angle = towards.angle_between(Vec2::from_angle(heading));
+        if miss_angle.abs() >= self.min_miss_angle {
+            return heading;
+        }
+        let side = if miss_angle < 0.0 { -1.0 } else { 1.0 };
+        towards.to_angle() + side * self.min_miss_angle
     }
 }
 
-pub fn create_asteroids(level: u32) -> Vec<Asteroid> {
+pub fn create_asteroids(
+    level: u32,
+    rules: &SpawnRules,
+    spawn_point: Vec2,
+    world: &World,
+    rng: &RandGenerator,
+) -> Vec<Asteroid> {
     let mut asteroids: Vec<Asteroid> = vec![];
     for _ in 0..level {
-        let start_point = vec2(
-            random_range(0.0, screen_width()),
-            random_range(0.0, screen_height()),
-        );
-        let rotation = random_range(0.0, 2.0 * std::f32::consts::PI);
-        asteroids.push(Asteroid::new(start_point, rotation, &AsteroidSizes::Large));
+        let size = AsteroidSizes::Large;
+        let outline = generate_asteroid_outline(&size, rng);
+        let start_point = rules.pick_point(spawn_point, outline.bounding_radius(), world, rng);
+        let heading = rng.gen_range(0.0, 2.0 * std::f32::consts::PI);
+        let heading = rules.steer_clear(start_point, heading, spawn_point, world);
+        let spin = rng.gen_range(-1.0, 1.0);
+        asteroids.push(Asteroid::new(start_point, heading, spin, &size, outline));
     }
     asteroids
 }
 
-pub fn split_asteroid(asteroid: &Asteroid) -> Vec<Asteroid> {
+pub fn split_asteroid(asteroid: &Asteroid, rng: &RandGenerator) -> Vec<Asteroid> {
     let mut new_asteroids: Vec<Asteroid> = vec![];
     let start_point = asteroid.body.point;
 
@@ -110,8 +302,199 @@ pub fn split_asteroid(asteroid: &Asteroid) -> Vec<Asteroid> {
     };
 
     for _ in 0..3 {
-        let rotation = random_range(0.0, 2.0 * std::f32::consts::PI);
-        new_asteroids.push(Asteroid::new(start_point, rotation, &next_size));
+        let heading = rng.gen_range(0.0, 2.0 * std::f32::consts::PI);
+        let spin = rng.gen_range(-1.0, 1.0);
+        let outline = generate_asteroid_outline(&next_size, rng);
+        let mut new_asteroid = Asteroid::new(start_point, heading, spin, &next_size, outline);
+        new_asteroid.body.screen_edge_behavior = asteroid.body.screen_edge_behavior.clone();
+        new_asteroids.push(new_asteroid);
     }
     new_asteroids
 }
+
+// Size class of an outline with `area`, for scoring and spin: the one whose
+// generated outlines come closest on average. Each size has about 4 times the
+// area of the next, so the thresholds sit halfway between on a log scale.
+fn get_asteroid_size_for_area(area: f32) -> AsteroidSizes {
+    let small = get_asteroid_area(&AsteroidSizes::Small);
+    let medium = get_asteroid_area(&AsteroidSizes::Medium);
+    let large = get_asteroid_area(&AsteroidSizes::Large);
+    if area >= (medium * large).sqrt() {
+        AsteroidSizes::Large
+    } else if area >= (small * medium).sqrt() {
+        AsteroidSizes::Medium
+    } else {
+        AsteroidSizes::Small
+    }
+}
+
+pub struct Fracture {
+    pub fragments: Vec<Asteroid>,
+    // world space pieces too small to keep flying
+    pub dust: Vec<Polygon>,
+}
+
+// Cuts the asteroid's actual outline along the line a bullet flew, through
+// `line_point` in `direction`. Each piece keeps its share of the mass and moves
+// with the parent (including its spin) plus a kick away from the cut and from
+// where the bullet entered.
+pub fn fracture_asteroid(asteroid: &Asteroid, line_point: Vec2, direction: Vec2) -> Fracture {
+    let mut fracture = Fracture {
+        fragments: vec![],
+        dust: vec![],
+    };
+    let outline = asteroid.shape.world();
+    let direction = direction.try_normalize().unwrap_or(Vec2::X);
+    let density = asteroid.body.mass / outline.area().max(f32::EPSILON);
+    // the point on the bullet's line closest to the asteroid's middle
+    let centroid = outline.centroid();
+    let closest = line_point + direction * (centroid - line_point).dot(direction);
+    // where the line first crosses the outline, cast from well behind the asteroid
+    let reach = 2.0 * asteroid.shape.bounding_radius();
+    let behind = closest - direction * reach;
+    let hit_point = match ray_cast(behind, direction, 2.0 * reach, outline) {
+        Some(distance) => behind + direction * distance,
+        None => closest,
+    };
+
+    let (mut left, mut right) = split_polygon(outline, hit_point, direction);
+    if left.is_empty() || right.is_empty() {
+        // the line only grazed the outline, break it through the middle instead
+        (left, right) = split_polygon(outline, centroid, direction);
+    }
+
+    let normal = direction.perp();
+    let pieces = left
+        .into_iter()
+        .map(|piece| (piece, 1.0))
+        .chain(right.into_iter().map(|piece| (piece, -1.0)));
+    for (piece, side) in pieces {
+        let area = piece.area();
+        if area < MIN_FRAGMENT_AREA {
+            fracture.dust.push(piece);
+            continue;
+        }
+        let piece_centroid = piece.centroid();
+        let size = get_asteroid_size_for_area(area);
+        let mut fragment = Asteroid::new(
+            piece_centroid,
+            0.0,
+            0.0,
+            &size,
+            piece.map(|point| point - piece_centroid),
+        );
+        let away_from_hit = (piece_centroid - hit_point)
+            .try_normalize()
+            .unwrap_or(Vec2::ZERO);
+        fragment.body.velocity = asteroid.body.velocity_at(piece_centroid)
+            + normal * side * FRACTURE_SPEED
+            + away_from_hit * HIT_SPEED;
+        fragment.body.angular_velocity = asteroid.body.angular_velocity;
+        fragment.body.mass = area * density;
+        let radius = fragment.shape.bounding_radius();
+        fragment.body.moment_of_inertia = 0.5 * fragment.body.mass * radius * radius;
+        fragment.body.screen_edge_behavior = asteroid.body.screen_edge_behavior.clone();
+        fracture.fragments.push(fragment);
+    }
+    fracture
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZES: [AsteroidSizes; 3] = [
+        AsteroidSizes::Small,
+        AsteroidSizes::Medium,
+        AsteroidSizes::Large,
+    ];
+
+    #[test]
+    fn generated_outlines_average_the_expected_area() {
+        let rng = RandGenerator::new();
+        rng.srand(7);
+        for size in SIZES.iter() {
+            let count = 2000;
+            let mean = (0..count)
+                .map(|_| generate_asteroid_outline(size, &rng).area())
+                .sum::<f32>()
+                / count as f32;
+            let expected = get_asteroid_area(size);
+            assert!(
+                (mean - expected).abs() < 0.02 * expected,
+                "mean area {mean} is not about {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn generated_outlines_fall_in_their_own_size_class() {
+        let rng = RandGenerator::new();
+        rng.srand(11);
+        for size in SIZES.iter() {
+            for _ in 0..500 {
+                let area = generate_asteroid_outline(size, &rng).area();
+                let class = get_asteroid_size_for_area(area);
+                assert_eq!(get_asteroid_scale(&class), get_asteroid_scale(size));
+            }
+        }
+    }
+
+    #[test]
+    fn fracture_pushes_pieces_away_from_where_the_bullet_entered() {
+        let outline = Polygon::closed(vec![
+            vec2(-40.0, -40.0),
+            vec2(40.0, -40.0),
+            vec2(40.0, 40.0),
+            vec2(-40.0, 40.0),
+        ])
+        .unwrap();
+        let mut asteroid =
+            Asteroid::new(vec2(200.0, 200.0), 0.0, 0.0, &AsteroidSizes::Large, outline);
+        asteroid.body.velocity = Vec2::ZERO;
+
+        // flying right through the middle, it went in on the left side
+        let fracture = fracture_asteroid(&asteroid, vec2(230.0, 200.0), Vec2::X);
+        assert_eq!(fracture.fragments.len(), 2);
+        let mass: f32 = fracture.fragments.iter().map(|f| f.body.mass).sum();
+        assert!((mass - asteroid.body.mass).abs() < 1e-2);
+        for fragment in fracture.fragments.iter() {
+            assert!(fragment.body.velocity.x > 0.0);
+            // and apart from the cut
+            let side = fragment.body.point.y - 200.0;
+            assert!(fragment.body.velocity.y * side > 0.0);
+        }
+    }
+
+    #[test]
+    fn generated_outlines_are_simple_and_seeded() {
+        let rng = RandGenerator::new();
+        let replay = RandGenerator::new();
+        rng.srand(3);
+        replay.srand(3);
+        for size in SIZES.iter() {
+            for _ in 0..1000 {
+                let outline = generate_asteroid_outline(size, &rng);
+                assert!(outline.is_closed() && outline.is_simple());
+                let replayed = generate_asteroid_outline(size, &replay);
+                assert_eq!(outline.points(), replayed.points());
+            }
+        }
+    }
+
+    #[test]
+    fn broken_outlines_fall_back_to_the_reference() {
+        let reference = reference_asteroid_outline(&AsteroidSizes::Medium);
+        let bow_tie = vec![
+            vec2(0.0, 0.0),
+            vec2(20.0, 20.0),
+            vec2(20.0, 0.0),
+            vec2(0.0, 20.0),
+        ];
+        let not_finite = vec![vec2(0.0, 0.0), vec2(f32::NAN, 0.0), vec2(0.0, 20.0)];
+        for points in [bow_tie, not_finite, vec![]] {
+            let outline = outline_or_reference(points, &AsteroidSizes::Medium);
+            assert_eq!(outline.points(), reference.points());
+        }
+    }
+}
@@ -1,33 +1,120 @@
 use crate::physics::body::*;
+use crate::physics::broadphase::Aabb;
+use crate::physics::collision::swept_segment_intersects_polygon;
+use crate::physics::polygon::Polygon;
+use crate::physics::world::World;
+use crate::render::renderer::Renderer;
 use crate::render::shape::*;
 use macroquad::prelude::*;
 
+// pixels a bullet flies before it fizzles out, a bit less than the screen width
+// like the arcade original, so wrapping bullets can't circle back to the ship
+const BULLET_RANGE: f32 = 600.0;
+
 pub struct Bullet {
     pub body: Body,
     pub shape: Shape,
+    // radians per second it can turn towards a target, 0 flies straight
+    pub homing_turn_rate: f32,
 }
 
 impl Bullet {
-    pub fn new(start_point: Vec2, rotation: f32, velocity: f32) -> Bullet {
-        Bullet {
+    pub fn new(
+        start_point: Vec2,
+        rotation: f32,
+        velocity: f32,
+        screen_edge_behavior: ScreenEdgeBehavior,
+    ) -> Bullet {
+        let mut bullet = Bullet {
             body: Body {
-                rotation,
-                point: start_point,
-                velocity: vec2(velocity * rotation.cos(), velocity * rotation.sin()),
-                acceleration: vec2(0.0, 0.0),
-                drag_coefficient: 1.0,
-                screen_edge_behavior: ScreenEdgeBehavior::Destroy,
-                destroyed: false,
-            },
-            shape: Shape {
-                points: vec![vec2(0.0, 0.0), vec2(5.0, 0.0)],
-                color: Color::new(29.0 / 255.0, 240.0 / 255.0, 233.0 / 255.0, 1.0),
-                thickness: 4.0,
+                range: Some(BULLET_RANGE),
+                ..Body::new(
+                    start_point,
+                    rotation,
+                    vec2(velocity * rotation.cos(), velocity * rotation.sin()),
+                    screen_edge_behavior,
+                )
             },
+            shape: Shape::new(
+                Polygon::open(vec![vec2(0.0, 0.0), vec2(5.0, 0.0)])
+                    .expect("bullet streak is a valid polyline"),
+                Color::new(29.0 / 255.0, 240.0 / 255.0, 233.0 / 255.0, 1.0),
+                4.0,
+            ),
+            homing_turn_rate: 0.0,
+        };
+        bullet.update_transforms();
+        bullet
+    }
+
+    pub fn update_transforms(&mut self) {
+        self.shape.update_from_body(&self.body);
+    }
+
+    // Turns towards something `to_target` away as fast as `homing_turn_rate`
+    // allows, keeping its speed. Call before `Body::update`.
+    pub fn steer_towards(&mut self, to_target: Vec2, dt: f32) {
+        let heading = Vec2::from_angle(self.body.velocity.to_angle());
+        let max_turn = self.homing_turn_rate * dt;
+        let turn = heading.angle_between(to_target).clamp(-max_turn, max_turn);
+        let heading = Vec2::from_angle(turn).rotate(heading);
+        self.body.velocity = heading * self.body.velocity.length();
+        self.body.rotation = heading.to_angle();
+    }
+
+    // Covers the whole path since the previous step, see `hits`.
+    pub fn bounds(&self) -> Aabb {
+        let radius = self.shape.bounding_radius();
+        let extent = vec2(radius, radius);
+        let path = Aabb::from_points(&[self.body.previous_point, self.body.point]);
+        Aabb {
+            min: path.min - extent,
+            max: path.max + extent,
         }
     }
 
-    pub fn render(&self) {
-        self.shape.draw(self.body.point, self.body.rotation);
+    // Checks everything the bullet passed through since the previous step,
+    // not just where it ended up.
+    // `offset` moves the bullet's path, e.g. next to a copy across a wrapped edge.
+    pub fn hits(&self, polygon: &Polygon, offset: Vec2) -> bool {
+        let ends = |points: &[Vec2]| (points[0] + offset, points[points.len() - 1] + offset);
+        let local = self.shape.points.points();
+        let previous_rotation = Vec2::from_angle(self.body.previous_rotation);
+        let previous = ends(&[
+            self.body.previous_point + previous_rotation.rotate(local[0]),
+            self.body.previous_point + previous_rotation.rotate(local[local.len() - 1]),
+        ]);
+        let current = ends(self.shape.world().points());
+        swept_segment_intersects_polygon(previous, current, polygon)
+    }
+
+    pub fn render(&self, renderer: &mut dyn Renderer, alpha: f32, world: &World) {
+        self.shape.render(renderer, &self.body, alpha, world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bullet_passing_through_a_small_asteroid_in_one_step_hits_it() {
+        let world = World::new(800.0, 600.0);
+        // 50 pixels a step at 120 steps per second
+        let mut bullet = Bullet::new(vec2(100.0, 100.0), 0.0, 6000.0, ScreenEdgeBehavior::Wrap);
+        let rock = Polygon::closed(vec![
+            vec2(120.0, 96.0),
+            vec2(128.0, 96.0),
+            vec2(128.0, 104.0),
+            vec2(120.0, 104.0),
+        ])
+        .unwrap();
+        bullet.body.save_previous();
+        bullet.body.update(1.0 / 120.0, &world);
+        bullet.update_transforms();
+        // ends up well past the rock
+        assert!(bullet.shape.world().points()[0].x > 128.0);
+        assert!(bullet.hits(&rock, Vec2::ZERO));
+        assert!(!bullet.hits(&rock, vec2(0.0, 10.0)));
     }
 }
@@ -1,3 +1,6 @@
 pub mod asteroid;
 pub mod bullet;
+pub mod particles;
+pub mod saucer;
 pub mod space_ship;
+pub mod weapon;
@@ -0,0 +1,267 @@
+use crate::physics::body::*;
+use crate::physics::polygon::Polygon;
+use crate::physics::world::World;
+use crate::render::renderer::Renderer;
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+
+pub const MAX_PARTICLES: usize = 1024;
+
+const DEBRIS_SPEED: f32 = 40.0; // pixels per second away from the middle, at most
+const DEBRIS_SPIN: f32 = 4.0; // radians per second, at most
+const DEBRIS_LINEAR_DRAG: f32 = 0.5;
+const SPARK_SPEED: f32 = 180.0;
+const SPARK_LINEAR_DRAG: f32 = 3.0;
+const SPARK_LENGTH: f32 = 4.0;
+const EXHAUST_SPEED: f32 = 150.0; // relative to the ship
+const EXHAUST_SPREAD: f32 = 0.3; // radians either side of straight back
+const EXHAUST_LENGTH: f32 = 3.0;
+const BEAM_GLOW_TIME: f32 = 0.15; // seconds a beam takes to fade
+
+// A line segment that flies, spins and fades out. Moves with a `Body`, so it
+// wraps, bounces or leaves the screen exactly like the thing it came from.
+pub struct Particle {
+    pub body: Body,
+    // from the body's point to one end of the segment, before rotation
+    pub half_extent: Vec2,
+    pub color: Color,
+    pub thickness: f32,
+}
+
+impl Particle {
+    // 1 when spawned, down to 0 at the end of its lifetime.
+    pub fn fade(&self) -> f32 {
+        match self.body.lifetime {
+            Some(lifetime) if lifetime > 0.0 => (1.0 - self.body.age / lifetime).clamp(0.0, 1.0),
+            _ => 1.0,
+        }
+    }
+
+    pub fn render(&self, renderer: &mut dyn Renderer, alpha: f32, world: &World) {
+        let (point, rotation) = self.body.interpolated(alpha);
+        let half_extent = Vec2::from_angle(rotation).rotate(self.half_extent);
+        let color = Color {
+            a: self.color.a * self.fade(),
+            ..self.color
+        };
+        for offset in self
+            .body
+            .wrap_offsets(point, self.half_extent.length(), world)
+        {
+            let point = point + offset;
+            renderer.line(
+                point - half_extent,
+                point + half_extent,
+                self.thickness,
+                color,
+            );
+        }
+    }
+}
+
+// Fixed size pool of particles. Expired slots are reused, so emitting every
+// step never allocates, and once the pool is full new particles are dropped.
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    // indices of expired particles, ready for reuse
+    free: Vec<usize>,
+    capacity: usize,
+}
+
+impl ParticleSystem {
+    pub fn new(capacity: usize) -> ParticleSystem {
+        ParticleSystem {
+            particles: Vec::with_capacity(capacity),
+            free: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    // Returns false if the pool is full.
+    pub fn spawn(&mut self, particle: Particle) -> bool {
+        if let Some(i) = self.free.pop() {
+            self.particles[i] = particle;
+        } else if self.particles.len() < self.capacity {
+            self.particles.push(particle);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    pub fn live_count(&self) -> usize {
+        self.particles.len() - self.free.len()
+    }
+
+    pub fn save_previous(&mut self) {
+        self.particles
+            .iter_mut()
+            .filter(|p| !p.body.destroyed)
+            .for_each(|p| p.body.save_previous());
+    }
+
+    pub fn update(&mut self, dt: f32, world: &World) {
+        for (i, particle) in self.particles.iter_mut().enumerate() {
+            if particle.body.destroyed {
+                continue;
+            }
+            particle.body.update(dt, world);
+            if particle.body.destroyed {
+                self.free.push(i);
+            }
+        }
+    }
+
+    pub fn render(&self, renderer: &mut dyn Renderer, alpha: f32, world: &World) {
+        self.particles
+            .iter()
+            .filter(|p| !p.body.destroyed)
+            .for_each(|p| p.render(renderer, alpha, world));
+    }
+
+    // Breaks every edge of a world space outline off into its own spinning
+    // segment. They keep the velocity `source` had at that spot, spin included.
+    pub fn emit_debris(
+        &mut self,
+        outline: &Polygon,
+        color: Color,
+        source: &Body,
+        rng: &RandGenerator,
+    ) {
+        let middle = outline.centroid();
+        for (start, end) in outline.edges() {
+            let point = (start + end) / 2.0;
+            let outwards = (point - middle).try_normalize().unwrap_or(Vec2::X);
+            let velocity =
+                source.velocity_at(point) + outwards * rng.gen_range(0.2, 1.0) * DEBRIS_SPEED;
+            self.spawn(Particle {
+                body: Body {
+                    angular_velocity: rng.gen_range(-DEBRIS_SPIN, DEBRIS_SPIN),
+                    linear_drag: DEBRIS_LINEAR_DRAG,
+                    lifetime: Some(rng.gen_range(0.8, 1.6)),
+                    ..Body::new(point, 0.0, velocity, source.screen_edge_behavior.clone())
+                },
+                half_extent: (end - start) / 2.0,
+                color,
+                thickness: 2.0,
+            });
+        }
+    }
+
+    // Short bright streaks flying out of `point` in every direction, carried
+    // along by `source` and meeting the screen edges the way it does.
+    pub fn emit_sparks(
+        &mut self,
+        point: Vec2,
+        source: &Body,
+        count: usize,
+        color: Color,
+        rng: &RandGenerator,
+    ) {
+        for _ in 0..count {
+            let direction = Vec2::from_angle(rng.gen_range(0.0, 2.0 * std::f32::consts::PI));
+            let speed = rng.gen_range(0.3, 1.0) * SPARK_SPEED;
+            self.spawn(Particle {
+                body: Body {
+                    linear_drag: SPARK_LINEAR_DRAG,
+                    lifetime: Some(rng.gen_range(0.2, 0.5)),
+                    ..Body::new(
+                        point,
+                        direction.to_angle(),
+                        source.velocity + direction * speed,
+                        source.screen_edge_behavior.clone(),
+                    )
+                },
+                half_extent: vec2(SPARK_LENGTH / 2.0, 0.0),
+                color,
+                thickness: 1.0,
+            });
+        }
+    }
+
+    // One puff of the ship's trail, blown backwards out of the nozzle at `point`.
+    pub fn emit_exhaust(&mut self, point: Vec2, ship: &Body, rng: &RandGenerator) {
+        let angle =
+            ship.rotation + std::f32::consts::PI + rng.gen_range(-EXHAUST_SPREAD, EXHAUST_SPREAD);
+        let speed = rng.gen_range(0.5, 1.0) * EXHAUST_SPEED;
+        let color = if rng.gen_range(0, 100) < 66 {
+            YELLOW
+        } else {
+            RED
+        };
+        self.spawn(Particle {
+            body: Body {
+                lifetime: Some(rng.gen_range(0.2, 0.4)),
+                ..Body::new(
+                    point,
+                    angle,
+                    ship.velocity + Vec2::from_angle(angle) * speed,
+                    ship.screen_edge_behavior.clone(),
+                )
+            },
+            half_extent: vec2(EXHAUST_LENGTH / 2.0, 0.0),
+            color,
+            thickness: 1.0,
+        });
+    }
+
+    // A beam from `start` to `end` that stays put and fades quickly.
+    pub fn emit_beam(
+        &mut self,
+        start: Vec2,
+        end: Vec2,
+        color: Color,
+        screen_edge_behavior: ScreenEdgeBehavior,
+    ) {
+        let middle = (start + end) / 2.0;
+        self.spawn(Particle {
+            body: Body {
+                lifetime: Some(BEAM_GLOW_TIME),
+                ..Body::new(
+                    middle,
+                    (end - start).to_angle(),
+                    Vec2::ZERO,
+                    screen_edge_behavior,
+                )
+            },
+            half_extent: vec2(start.distance(end) / 2.0, 0.0),
+            color,
+            thickness: 2.0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn short_lived() -> Particle {
+        Particle {
+            body: Body {
+                lifetime: Some(0.1),
+                ..Body::new(Vec2::ZERO, 0.0, Vec2::ZERO, ScreenEdgeBehavior::Wrap)
+            },
+            half_extent: vec2(1.0, 0.0),
+            color: WHITE,
+            thickness: 1.0,
+        }
+    }
+
+    #[test]
+    fn expired_slots_are_reused_without_growing_the_pool() {
+        let world = World::new(800.0, 600.0);
+        let mut particles = ParticleSystem::new(2);
+        assert!(particles.spawn(short_lived()));
+        assert!(particles.spawn(short_lived()));
+        assert!(!particles.spawn(short_lived()), "the pool is full");
+        assert_eq!(particles.live_count(), 2);
+
+        particles.update(0.2, &world);
+        assert_eq!(particles.live_count(), 0);
+        assert!(particles.spawn(short_lived()));
+        assert!(particles.spawn(short_lived()));
+        assert!(!particles.spawn(short_lived()));
+        assert_eq!(particles.live_count(), 2);
+        assert_eq!(particles.particles.len(), 2);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::game::components::bullet::Bullet;
+use crate::physics::body::*;
+use crate::physics::broadphase::BoundingCircle;
+use crate::physics::polygon::Polygon;
+use crate::physics::world::World;
+use crate::render::renderer::Renderer;
+use crate::render::shape::*;
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+
+const SAUCER_BULLET_VEL: f32 = 300.0;
+const SAUCER_TURN_INTERVAL: f32 = 1.5; // seconds between direction changes
+const SAUCER_BULLET_COLOR: Color = RED;
+// seconds until the first saucer of a level, the wait gets shorter the longer
+// the level goes on, down to the minimum
+const SAUCER_INTERVAL: f32 = 20.0;
+const SAUCER_MIN_INTERVAL: f32 = 6.0;
+const SAUCER_INTERVAL_SHRINK: f32 = 0.25; // seconds less per second played
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaucerSizes {
+    // fires in random directions
+    Large,
+    // aims where the ship is going to be
+    Small,
+}
+
+fn get_saucer_scale(size: &SaucerSizes) -> f32 {
+    match size {
+        SaucerSizes::Large => 1.0,
+        SaucerSizes::Small => 0.6,
+    }
+}
+
+fn get_saucer_speed(size: &SaucerSizes) -> f32 {
+    match size {
+        SaucerSizes::Large => 80.0,
+        SaucerSizes::Small => 120.0,
+    }
+}
+
+// seconds between shots
+fn get_saucer_fire_interval(size: &SaucerSizes) -> f32 {
+    match size {
+        SaucerSizes::Large => 1.0,
+        SaucerSizes::Small => 0.8,
+    }
+}
+
+// Seconds to wait for the next saucer, `level_time` seconds into a level.
+pub fn get_saucer_interval(level_time: f32) -> f32 {
+    (SAUCER_INTERVAL - level_time * SAUCER_INTERVAL_SHRINK).max(SAUCER_MIN_INTERVAL)
+}
+
+pub fn get_saucer_score(size: &SaucerSizes) -> u32 {
+    match size {
+        SaucerSizes::Large => 5,
+        SaucerSizes::Small => 10,
+    }
+}
+
+pub struct Saucer {
+    pub body: Body,
+    pub shape: Shape,
+    pub size: SaucerSizes,
+    // across the screen once, then it leaves
+    pub horizontal_travelled: f32,
+    pub fire_in: f32,
+    pub turn_in: f32,
+}
+
+impl Saucer {
+    // `direction` is 1 to fly right, -1 to fly left.
+    pub fn new(start_point: Vec2, direction: f32, size: &SaucerSizes) -> Saucer {
+        let scale = get_saucer_scale(size);
+        let outline = [
+            vec2(-16.0, 0.0),
+            vec2(-8.0, -5.0),
+            vec2(-5.0, -10.0),
+            vec2(5.0, -10.0),
+            vec2(8.0, -5.0),
+            vec2(16.0, 0.0),
+            vec2(8.0, 6.0),
+            vec2(-8.0, 6.0),
+        ];
+        let mut saucer = Saucer {
+            body: Body::new(
+                start_point,
+                0.0,
+                vec2(direction * get_saucer_speed(size), 0.0),
+                ScreenEdgeBehavior::Wrap,
+            ),
+            shape: Shape::new(
+                Polygon::closed(outline.iter().map(|point| *point * scale).collect())
+                    .expect("saucer outline is a valid polygon"),
+                WHITE,
+                2.0,
+            ),
+            size: size.clone(),
+            horizontal_travelled: 0.0,
+            fire_in: get_saucer_fire_interval(size),
+            turn_in: SAUCER_TURN_INTERVAL,
+        };
+        saucer.body.extent = saucer.shape.bounding_radius();
+        saucer.update_transforms();
+        saucer
+    }
+
+    // How far it flies sideways before it leaves: across the screen once, or
+    // from wall to wall when it can't wrap around.
+    pub fn course_length(&self, world: &World) -> f32 {
+        match self.body.screen_edge_behavior {
+            ScreenEdgeBehavior::Wrap => world.width,
+            _ => world.width - 2.0 * self.body.extent,
+        }
+    }
+
+    pub fn update_transforms(&mut self) {
+        self.shape.update_from_body(&self.body);
+    }
+
+    pub fn bounding_circle(&self) -> BoundingCircle {
+        BoundingCircle {
+            center: self.body.point,
+            radius: self.shape.bounding_radius(),
+        }
+    }
+
+    // Flies, zigzags and fires. `target` is the ship's body if it can be shot at.
+    // Returns the bullet fired this step, if any.
+    pub fn update(
+        &mut self,
+        dt: f32,
+        world: &World,
+        target: Option<&Body>,
+        rng: &RandGenerator,
+    ) -> Option<Bullet> {
+        self.turn_in -= dt;
+        if self.turn_in <= 0.0 {
+            self.turn_in += SAUCER_TURN_INTERVAL;
+            // up, down or straight on, keeping the horizontal speed
+            let speed = get_saucer_speed(&self.size);
+            self.body.velocity.y = rng.gen_range(-1, 2) as f32 * speed * 0.5;
+        }
+        self.body.update(dt, world);
+        self.horizontal_travelled += self.body.velocity.x.abs() * dt;
+        if self.horizontal_travelled >= self.course_length(world) {
+            self.body.destroyed = true;
+        }
+
+        self.fire_in -= dt;
+        if self.fire_in > 0.0 || self.body.destroyed {
+            return None;
+        }
+        self.fire_in += get_saucer_fire_interval(&self.size);
+        let angle = match (&self.size, target) {
+            (SaucerSizes::Small, Some(target)) => {
+                let to_target = world.wrapped_delta(self.body.point, target.point);
+                intercept_direction(to_target, target.velocity, SAUCER_BULLET_VEL).to_angle()
+            }
+            _ => rng.gen_range(0.0, 2.0 * std::f32::consts::PI),
+        };
+        let mut bullet = Bullet::new(
+            self.body.point,
+            angle,
+            SAUCER_BULLET_VEL,
+            ScreenEdgeBehavior::Wrap,
+        );
+        bullet.shape.color = SAUCER_BULLET_COLOR;
+        Some(bullet)
+    }
+
+    pub fn render(&self, renderer: &mut dyn Renderer, alpha: f32, world: &World) {
+        self.shape.render(renderer, &self.body, alpha, world);
+    }
+}
+
+// Direction to shoot at `speed` to hit something `to_target` away moving at
+// `target_velocity`, or straight at it if the shot can't catch up.
+// Solves |to_target + target_velocity * t| = speed * t for the earliest t > 0.
+pub fn intercept_direction(to_target: Vec2, target_velocity: Vec2, speed: f32) -> Vec2 {
+    let a = target_velocity.dot(target_velocity) - speed * speed;
+    let b = 2.0 * to_target.dot(target_velocity);
+    let c = to_target.dot(to_target);
+    let time = if a.abs() < f32::EPSILON {
+        -c / b
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            -1.0
+        } else {
+            let root = discriminant.sqrt();
+            let (t1, t2) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+            match (t1 > 0.0, t2 > 0.0) {
+                (true, true) => t1.min(t2),
+                (true, false) => t1,
+                (false, true) => t2,
+                (false, false) => -1.0,
+            }
+        }
+    };
+    if time.is_finite() && time > 0.0 {
+        to_target + target_velocity * time
+    } else {
+        to_target
+    }
+}
+
+// A saucer entering from the left or right edge. The first level only sends
+// large ones, after that small ones become more likely level by level.
+pub fn create_saucer(level: u32, world: &World, rng: &RandGenerator) -> Saucer {
+    let small_chance = (0.2 * level.saturating_sub(1) as f32).min(0.8);
+    let size = if rng.gen_range(0.0, 1.0) < small_chance {
+        SaucerSizes::Small
+    } else {
+        SaucerSizes::Large
+    };
+    let direction = if rng.gen_range(0, 2) == 0 { 1.0 } else { -1.0 };
+    let x = if direction > 0.0 { 0.0 } else { world.width };
+    let y = rng.gen_range(world.height * 0.1, world.height * 0.9);
+    Saucer::new(vec2(x, y), direction, &size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stationary_target_is_shot_straight_at() {
+        let aim = intercept_direction(vec2(30.0, 40.0), Vec2::ZERO, 200.0);
+        assert!(aim.normalize().distance(vec2(0.6, 0.8)) < 1e-5);
+    }
+
+    #[test]
+    fn shot_meets_a_crossing_target() {
+        let to_target = vec2(100.0, 0.0);
+        let target_velocity = vec2(0.0, 50.0);
+        let speed = 200.0;
+        let aim = intercept_direction(to_target, target_velocity, speed);
+        // leads the target
+        assert!(aim.y > 0.0);
+        // both get to the same point at the same time
+        let time = aim.length() / speed;
+        let target_then = to_target + target_velocity * time;
+        assert!(target_then.distance(aim.normalize() * speed * time) < 1e-3);
+    }
+
+    #[test]
+    fn target_faster_than_the_shot_is_shot_straight_at() {
+        let to_target = vec2(100.0, 0.0);
+        // running away faster than the shot flies
+        let aim = intercept_direction(to_target, vec2(300.0, 100.0), 200.0);
+        assert_eq!(aim, to_target);
+    }
+}
@@ -1,13 +1,66 @@
+use crate::game::components::weapon::*;
 use crate::physics::body::*;
+use crate::physics::broadphase::BoundingCircle;
+use crate::physics::polygon::Polygon;
+use crate::physics::world::World;
+use crate::render::renderer::Renderer;
+use crate::render::scene::SceneNode;
 use crate::render::shape::*;
+use crate::render::transform::Transform2D;
 use macroquad::prelude::*;
 
+const SHIP_LINEAR_DRAG: f32 = 0.6; // decay rate per second, about 1% per frame at 60 fps
+const SHIP_ANGULAR_DRAG: f32 = 8.0; // decay rate per second
+const BLINK_HZ: f32 = 8.0; // while invulnerable
+
+// seconds to shrink away, to cross hyperspace unseen and to grow back
+const HYPERSPACE_FADE_TIME: f32 = 0.3;
+const HYPERSPACE_TRANSIT_TIME: f32 = 0.4;
+
+// A jump in progress. Where it ends and whether it goes wrong are decided
+// when it starts, from the gameplay random stream, so replays match.
+#[derive(Debug, Clone)]
+pub struct HyperspaceJump {
+    pub destination: Vec2,
+    pub fails: bool,
+    pub elapsed: f32,
+}
+
+impl HyperspaceJump {
+    const DURATION: f32 = 2.0 * HYPERSPACE_FADE_TIME + HYPERSPACE_TRANSIT_TIME;
+
+    // How much of the ship is there, 1 when it leaves and arrives, 0 in transit.
+    pub fn materialized(&self) -> f32 {
+        let arrival = self.elapsed - HYPERSPACE_FADE_TIME - HYPERSPACE_TRANSIT_TIME;
+        if self.elapsed < HYPERSPACE_FADE_TIME {
+            1.0 - self.elapsed / HYPERSPACE_FADE_TIME
+        } else if arrival < 0.0 {
+            0.0
+        } else {
+            (arrival / HYPERSPACE_FADE_TIME).min(1.0)
+        }
+    }
+
+    // Still at the point it jumped from.
+    pub fn departing(&self) -> bool {
+        self.elapsed < HYPERSPACE_FADE_TIME + HYPERSPACE_TRANSIT_TIME / 2.0
+    }
+}
+
 pub struct SpaceShip {
     pub body: Body,
     pub ship_shape: Shape,
-    pub flames_shape_base: Shape,
-    pub flames_shape_extended: Shape,
+    // tip of the hull before rotation, where shots come from
+    pub nose: Vec2,
+    // attached to the ship's nozzle, moves and turns with the body
+    pub flames: SceneNode,
     pub is_thrusting: bool,
+    // seconds left in which asteroids pass through the ship, it blinks meanwhile
+    pub invulnerable_for: f32,
+    pub hyperspace: Option<HyperspaceJump>,
+    // seconds until the next jump is possible
+    pub hyperspace_cooldown: f32,
+    pub weapon: Weapon,
 }
 
 impl SpaceShip {
@@ -18,68 +71,151 @@ impl SpaceShip {
         let bottom_right = vec2(-width / 2.0, height / 2.0);
         let bottom_center = vec2(-width / 4.0, 0.0);
         let bottom_left = vec2(-width / 2.0, -height / 2.0);
-        // flames base
-        let flames_base_left = vec2(-width * 1.2, -height / 2.0);
-        let flames_base_center = vec2(-width * 0.5, 0.0);
-        let flames_base_right = vec2(-width * 1.2, height / 2.0);
-        // flames extended
-        let flames_extended_left = vec2(-width * 1.5, -height / 4.0);
-        let flames_extended_center = vec2(-width * 0.5, 0.0);
-        let flames_extended_right = vec2(-width * 1.5, height / 4.0);
-
-        SpaceShip {
+        // flames, relative to the nozzle
+        let nozzle = vec2(-width * 0.5, 0.0);
+        let flames_base_left = vec2(-width * 0.7, -height / 2.0);
+        let flames_base_right = vec2(-width * 0.7, height / 2.0);
+
+        let mut space_ship = SpaceShip {
             body: Body {
-                rotation: 0.0,
-                point: start_point,
-                velocity: vec2(0.0, 0.0),
-                acceleration: vec2(0.0, 0.0),
-                drag_coefficient: 0.99,
-                screen_edge_behavior: ScreenEdgeBehavior::Wrap,
-                destroyed: false,
-            },
-            ship_shape: Shape {
-                points: vec![
-                    top_center,
-                    bottom_right,
-                    bottom_center,
-                    bottom_left,
-                    top_center,
-                ],
-                color: WHITE,
-                thickness: 2.0,
-            },
-            flames_shape_base: Shape {
-                points: vec![flames_base_left, flames_base_center, flames_base_right],
-                color: RED,
-                thickness: 2.0,
-            },
-            flames_shape_extended: Shape {
-                points: vec![
-                    flames_extended_left,
-                    flames_extended_center,
-                    flames_extended_right,
-                ],
-                color: YELLOW,
-                thickness: 2.0,
+                linear_drag: SHIP_LINEAR_DRAG,
+                // turning stops quickly once the key is released
+                angular_drag: SHIP_ANGULAR_DRAG,
+                ..Body::new(start_point, 0.0, vec2(0.0, 0.0), ScreenEdgeBehavior::Wrap)
             },
+            ship_shape: Shape::new(
+                Polygon::closed(vec![top_center, bottom_right, bottom_center, bottom_left])
+                    .expect("ship outline is a valid polygon"),
+                WHITE,
+                2.0,
+            ),
+            nose: top_center,
+            flames: SceneNode::new(Transform2D::new(nozzle, 0.0), None).with_child(SceneNode::new(
+                Transform2D::IDENTITY,
+                Some(Shape::new(
+                    Polygon::open(vec![flames_base_left, Vec2::ZERO, flames_base_right])
+                        .expect("flames are a valid polyline"),
+                    RED,
+                    2.0,
+                )),
+            )),
             is_thrusting: false,
+            invulnerable_for: 0.0,
+            hyperspace: None,
+            hyperspace_cooldown: 0.0,
+            weapon: Weapon::new(WeaponKind::Blaster),
+        };
+        space_ship.body.extent = space_ship.ship_shape.bounding_radius();
+        space_ship.flames.visible = false;
+        space_ship.update_transforms();
+        space_ship
+    }
+
+    pub fn update_transforms(&mut self) {
+        let (point, rotation) = (self.body.point, self.body.rotation);
+        match &self.hyperspace {
+            None => {
+                self.ship_shape.update_world(point, rotation);
+                self.flames
+                    .update_world(&Transform2D::new(point, rotation).matrix());
+            }
+            Some(jump) => {
+                // shrinks into and grows out of a point
+                let matrix = Transform2D::new(point, rotation)
+                    .with_scale(Vec2::splat(jump.materialized()))
+                    .matrix();
+                self.ship_shape.update_world_matrix(&matrix);
+                self.flames.update_world(&matrix);
+            }
+        }
+    }
+
+    pub fn can_jump(&self) -> bool {
+        self.hyperspace.is_none() && self.hyperspace_cooldown <= 0.0
+    }
+
+    pub fn start_hyperspace(&mut self, destination: Vec2, fails: bool, cooldown: f32) {
+        self.apply_thrust(0.0);
+        self.hyperspace = Some(HyperspaceJump {
+            destination,
+            fails,
+            elapsed: 0.0,
+        });
+        self.hyperspace_cooldown = cooldown;
+    }
+
+    // Runs the jump in place of `Body::update`. Returns the jump once it is over,
+    // it's up to the caller to blow the ship up if it `fails`.
+    pub fn update_hyperspace(&mut self, dt: f32) -> Option<HyperspaceJump> {
+        let jump = self.hyperspace.as_mut()?;
+        let was_departing = jump.departing();
+        jump.elapsed += dt;
+        if was_departing && !jump.departing() {
+            // halfway through transit, nobody sees it move
+            self.body.point = jump.destination;
+            self.body.previous_point = jump.destination;
+            self.body.velocity = Vec2::ZERO;
+            self.body.angular_velocity = 0.0;
+        }
+        if jump.elapsed < HyperspaceJump::DURATION {
+            return None;
         }
+        self.hyperspace.take()
     }
 
+    // The exhaust trail behind the flame is emitted by the caller, see `nozzle`.
     pub fn apply_thrust(&mut self, thrust: f32) {
         self.body.apply_thrust(thrust);
         self.is_thrusting = thrust > 0.0;
+        self.flames.visible = self.is_thrusting;
     }
 
-    pub fn render(&self) {
-        self.ship_shape.draw(self.body.point, self.body.rotation);
-        if self.is_thrusting {
-            self.flames_shape_base
-                .draw(self.body.point, self.body.rotation);
-            if rand::gen_range(0, 100) < 66 {
-                self.flames_shape_extended
-                    .draw(self.body.point, self.body.rotation);
-            }
+    // World space point the exhaust comes out of.
+    pub fn nozzle(&self) -> Vec2 {
+        self.body.point + Vec2::from_angle(self.body.rotation).rotate(self.flames.local.translation)
+    }
+
+    // World space point the weapon's shots start at.
+    pub fn muzzle(&self) -> Vec2 {
+        let local = self.nose + vec2(self.weapon.muzzle_offset, 0.0);
+        self.body.point + Vec2::from_angle(self.body.rotation).rotate(local)
+    }
+
+    // Check `weapon.ready` first.
+    pub fn fire(&mut self, screen_edge_behavior: ScreenEdgeBehavior) -> Shot {
+        let muzzle = self.muzzle();
+        self.weapon.fire(
+            muzzle,
+            self.body.rotation,
+            self.body.velocity,
+            screen_edge_behavior,
+        )
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_for > 0.0
+    }
+
+    pub fn bounding_circle(&self) -> BoundingCircle {
+        BoundingCircle {
+            center: self.body.point,
+            radius: self.ship_shape.bounding_radius(),
+        }
+    }
+
+    pub fn render(&self, renderer: &mut dyn Renderer, alpha: f32, world: &World) {
+        // off for every other blink, counted from the end so it always finishes visible
+        if (self.invulnerable_for * BLINK_HZ) as u32 % 2 == 1 {
+            return;
+        }
+        let (point, _) = self.body.interpolated(alpha);
+        let interpolation = self.body.interpolation_transform(alpha);
+        // the flames reach further back than the hull
+        let radius = self.flames.bounding_radius();
+        for offset in self.body.wrap_offsets(point, radius, world) {
+            let transform = Mat3::from_translation(offset) * interpolation;
+            self.ship_shape.draw(renderer, &transform);
+            self.flames.draw(renderer, &transform);
         }
     }
 }
@@ -0,0 +1,177 @@
+use crate::game::components::bullet::Bullet;
+use crate::physics::body::ScreenEdgeBehavior;
+use crate::physics::polygon::Polygon;
+use crate::render::shape::Shape;
+use macroquad::prelude::*;
+
+const SPREAD_ANGLE: f32 = 0.2; // radians between the shots of a spread
+const MISSILE_TURN_RATE: f32 = 3.0; // radians per second
+const MISSILE_COLOR: Color = ORANGE;
+const MISSILE_LENGTH: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeaponKind {
+    // one bullet at a time, like the arcade original
+    Blaster,
+    // a fan of three bullets
+    Spread,
+    // an instant beam through everything in its way
+    Laser,
+    // slow shots that turn towards the nearest target
+    Missile,
+}
+
+impl WeaponKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeaponKind::Blaster => "blaster",
+            WeaponKind::Spread => "spread",
+            WeaponKind::Laser => "laser",
+            WeaponKind::Missile => "missile",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<WeaponKind> {
+        match name {
+            "blaster" => Some(WeaponKind::Blaster),
+            "spread" => Some(WeaponKind::Spread),
+            "laser" => Some(WeaponKind::Laser),
+            "missile" => Some(WeaponKind::Missile),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> WeaponKind {
+        match self {
+            WeaponKind::Blaster => WeaponKind::Spread,
+            WeaponKind::Spread => WeaponKind::Laser,
+            WeaponKind::Laser => WeaponKind::Missile,
+            WeaponKind::Missile => WeaponKind::Blaster,
+        }
+    }
+}
+
+// What a weapon put out when it fired.
+pub enum Shot {
+    Projectiles(Vec<Bullet>),
+    // hits everything between the two points at once, it's up to the caller to ray cast
+    Beam { start: Vec2, end: Vec2 },
+}
+
+pub struct Weapon {
+    pub kind: WeaponKind,
+    // seconds between shots while the trigger is held
+    pub fire_interval: f32,
+    // bullets per shot
+    pub projectiles: usize,
+    // the player's bullets in flight at once, None for beams since they don't linger
+    pub max_live: Option<usize>,
+    // pixels per second on top of the ship's own velocity
+    pub projectile_speed: f32,
+    // seconds a projectile flies, a beam reaches as far as it would get in that time
+    pub projectile_lifetime: f32,
+    // pixels in front of the ship's nose that shots start at
+    pub muzzle_offset: f32,
+    // seconds until it can fire again
+    pub cooldown: f32,
+}
+
+impl Weapon {
+    pub fn new(kind: WeaponKind) -> Weapon {
+        let weapon = Weapon {
+            kind,
+            fire_interval: 0.15,
+            projectiles: 1,
+            max_live: Some(4),
+            projectile_speed: 500.0,
+            projectile_lifetime: 1.2,
+            muzzle_offset: 2.0,
+            cooldown: 0.0,
+        };
+        match kind {
+            WeaponKind::Blaster => weapon,
+            WeaponKind::Spread => Weapon {
+                fire_interval: 0.35,
+                projectiles: 3,
+                max_live: Some(9),
+                projectile_speed: 450.0,
+                projectile_lifetime: 0.8,
+                ..weapon
+            },
+            WeaponKind::Laser => Weapon {
+                fire_interval: 0.6,
+                max_live: None,
+                projectile_speed: 4000.0,
+                projectile_lifetime: 0.1,
+                muzzle_offset: 0.0,
+                ..weapon
+            },
+            WeaponKind::Missile => Weapon {
+                fire_interval: 0.8,
+                max_live: Some(2),
+                projectile_speed: 250.0,
+                projectile_lifetime: 3.0,
+                muzzle_offset: 6.0,
+                ..weapon
+            },
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.cooldown = (self.cooldown - dt).max(0.0);
+    }
+
+    // `live` is how many of the player's bullets are still flying.
+    pub fn ready(&self, live: usize) -> bool {
+        let room = match self.max_live {
+            Some(max_live) => live + self.projectiles <= max_live,
+            None => true,
+        };
+        self.cooldown <= 0.0 && room
+    }
+
+    // Shots leave `muzzle` along `rotation`, carried along by the shooter's `velocity`.
+    pub fn fire(
+        &mut self,
+        muzzle: Vec2,
+        rotation: f32,
+        velocity: Vec2,
+        screen_edge_behavior: ScreenEdgeBehavior,
+    ) -> Shot {
+        self.cooldown = self.fire_interval;
+        if self.kind == WeaponKind::Laser {
+            let reach = self.projectile_speed * self.projectile_lifetime;
+            return Shot::Beam {
+                start: muzzle,
+                end: muzzle + Vec2::from_angle(rotation) * reach,
+            };
+        }
+        // fanned out evenly around the ship's heading
+        let first = -SPREAD_ANGLE * (self.projectiles - 1) as f32 / 2.0;
+        let bullets = (0..self.projectiles)
+            .map(|i| {
+                let mut bullet = Bullet::new(
+                    muzzle,
+                    rotation + first + i as f32 * SPREAD_ANGLE,
+                    self.projectile_speed,
+                    screen_edge_behavior.clone(),
+                );
+                bullet.body.velocity += velocity;
+                bullet.body.range = None;
+                bullet.body.lifetime = Some(self.projectile_lifetime);
+                if self.kind == WeaponKind::Missile {
+                    bullet.homing_turn_rate = MISSILE_TURN_RATE;
+                    bullet.shape = Shape::new(
+                        Polygon::open(vec![vec2(0.0, 0.0), vec2(MISSILE_LENGTH, 0.0)])
+                            .expect("missile streak is a valid polyline"),
+                        MISSILE_COLOR,
+                        3.0,
+                    );
+                    bullet.update_transforms();
+                }
+                bullet
+            })
+            .collect();
+        Shot::Projectiles(bullets)
+    }
+}
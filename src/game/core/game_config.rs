@@ -0,0 +1,174 @@
+use crate::game::components::weapon::WeaponKind;
+use crate::physics::body::ScreenEdgeBehavior;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    // asteroids pass through each other like the arcade original
+    Classic,
+    // asteroids have mass and bounce off each other
+    Sandbox,
+    // sandbox physics inside solid walls
+    Arena,
+}
+
+impl GameMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "classic",
+            GameMode::Sandbox => "sandbox",
+            GameMode::Arena => "arena",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        match name {
+            "classic" => Some(GameMode::Classic),
+            "sandbox" => Some(GameMode::Sandbox),
+            "arena" => Some(GameMode::Arena),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> GameMode {
+        match self {
+            GameMode::Classic => GameMode::Sandbox,
+            GameMode::Sandbox => GameMode::Arena,
+            GameMode::Arena => GameMode::Classic,
+        }
+    }
+
+    pub fn asteroid_collisions(&self) -> bool {
+        match self {
+            GameMode::Classic => false,
+            GameMode::Sandbox | GameMode::Arena => true,
+        }
+    }
+
+    pub fn asteroid_edge_behavior(&self) -> ScreenEdgeBehavior {
+        match self {
+            GameMode::Classic | GameMode::Sandbox => ScreenEdgeBehavior::Wrap,
+            GameMode::Arena => ScreenEdgeBehavior::Bounce,
+        }
+    }
+
+    pub fn ship_edge_behavior(&self) -> ScreenEdgeBehavior {
+        match self {
+            GameMode::Classic | GameMode::Sandbox => ScreenEdgeBehavior::Wrap,
+            GameMode::Arena => ScreenEdgeBehavior::Clamp,
+        }
+    }
+
+    pub fn saucer_edge_behavior(&self) -> ScreenEdgeBehavior {
+        match self {
+            GameMode::Classic | GameMode::Sandbox => ScreenEdgeBehavior::Wrap,
+            GameMode::Arena => ScreenEdgeBehavior::Bounce,
+        }
+    }
+
+    pub fn bullet_edge_behavior(&self) -> ScreenEdgeBehavior {
+        match self {
+            GameMode::Classic | GameMode::Sandbox => ScreenEdgeBehavior::Wrap,
+            GameMode::Arena => ScreenEdgeBehavior::Destroy,
+        }
+    }
+}
+
+// What happens to an asteroid that gets shot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DestructionModel {
+    // replaced by three smaller asteroids, like the arcade original
+    Split,
+    // cut in two along the bullet's path, small pieces turn to dust
+    Fracture,
+}
+
+impl DestructionModel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DestructionModel::Split => "split",
+            DestructionModel::Fracture => "fracture",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DestructionModel> {
+        match name {
+            "split" => Some(DestructionModel::Split),
+            "fracture" => Some(DestructionModel::Fracture),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> DestructionModel {
+        match self {
+            DestructionModel::Split => DestructionModel::Fracture,
+            DestructionModel::Fracture => DestructionModel::Split,
+        }
+    }
+}
+
+// Settings chosen before a run starts, from the command line or the main menu.
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    pub seed: u64,
+    pub mode: GameMode,
+    pub destruction: DestructionModel,
+    // the ship's weapon, also switchable while playing
+    pub weapon: WeaponKind,
+    // chance from 0 to 1 that a hyperspace jump blows the ship up on re-entry
+    pub hyperspace_failure_chance: f32,
+    // seconds after a jump before the next one
+    pub hyperspace_cooldown: f32,
+}
+
+impl GameConfig {
+    pub fn new(seed: u64) -> GameConfig {
+        GameConfig {
+            seed,
+            mode: GameMode::Classic,
+            destruction: DestructionModel::Split,
+            weapon: WeaponKind::Blaster,
+            hyperspace_failure_chance: 0.1,
+            hyperspace_cooldown: 3.0,
+        }
+    }
+
+    // Reads `--seed <n>`, `--mode <classic|sandbox|arena>`,
+    // `--destruction <split|fracture>`, `--weapon <blaster|spread|laser|missile>`,
+    // `--hyperspace-risk <0 to 1>` and `--hyperspace-cooldown <seconds>` from the
+    // command line arguments.
+    pub fn from_args(args: &[String], default_seed: u64) -> GameConfig {
+        let mut config = GameConfig::new(default_seed);
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--seed" {
+                if let Some(seed) = args.next().and_then(|value| value.parse().ok()) {
+                    config.seed = seed;
+                }
+            } else if arg == "--mode" {
+                if let Some(mode) = args.next().and_then(|value| GameMode::from_name(value)) {
+                    config.mode = mode;
+                }
+            } else if arg == "--destruction" {
+                if let Some(destruction) = args
+                    .next()
+                    .and_then(|value| DestructionModel::from_name(value))
+                {
+                    config.destruction = destruction;
+                }
+            } else if arg == "--weapon" {
+                if let Some(weapon) = args.next().and_then(|value| WeaponKind::from_name(value)) {
+                    config.weapon = weapon;
+                }
+            } else if arg == "--hyperspace-risk" {
+                if let Some(chance) = args.next().and_then(|value| value.parse::<f32>().ok()) {
+                    config.hyperspace_failure_chance = chance.clamp(0.0, 1.0);
+                }
+            } else if arg == "--hyperspace-cooldown" {
+                if let Some(cooldown) = args.next().and_then(|value| value.parse::<f32>().ok()) {
+                    config.hyperspace_cooldown = cooldown.max(0.0);
+                }
+            }
+        }
+        config
+    }
+}
@@ -0,0 +1,74 @@
+use macroquad::prelude::*;
+
+// Snapshot of the player's input for one update of the game state.
+// Built from the keyboard by `poll`, or by hand when driving the game headless.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputFrame {
+    pub rotate_left: bool,
+    pub rotate_right: bool,
+    pub thrust_forward: bool,
+    pub fire: bool,
+    // the fire key is down, weapons keep firing at their own rate
+    pub fire_held: bool,
+    pub hyperspace: bool,
+    pub confirm: bool,
+    pub escape: bool,
+    pub quit: bool,
+    pub reroll_seed: bool,
+    pub cycle_mode: bool,
+    pub cycle_destruction: bool,
+    pub cycle_weapon: bool,
+}
+
+impl InputFrame {
+    pub fn poll() -> InputFrame {
+        InputFrame {
+            rotate_left: is_key_down(KeyCode::Left) || is_key_down(KeyCode::A),
+            rotate_right: is_key_down(KeyCode::Right) || is_key_down(KeyCode::D),
+            thrust_forward: is_key_down(KeyCode::Up) || is_key_down(KeyCode::W),
+            // on press, so a tap shorter than a step still fires
+            fire: is_key_pressed(KeyCode::Space),
+            fire_held: is_key_down(KeyCode::Space),
+            hyperspace: is_key_released(KeyCode::H),
+            confirm: is_key_released(KeyCode::Enter),
+            escape: is_key_released(KeyCode::Escape),
+            quit: is_key_released(KeyCode::Q),
+            reroll_seed: is_key_released(KeyCode::R),
+            cycle_mode: is_key_released(KeyCode::M),
+            cycle_destruction: is_key_released(KeyCode::F),
+            cycle_weapon: is_key_released(KeyCode::E),
+        }
+    }
+
+    // Folds a newer poll into this one. Held keys take the newest value, one-shot
+    // presses are kept until `clear_presses` so frames without a simulation step
+    // don't drop them.
+    pub fn merge(&mut self, newer: &InputFrame) {
+        self.rotate_left = newer.rotate_left;
+        self.rotate_right = newer.rotate_right;
+        self.thrust_forward = newer.thrust_forward;
+        self.fire_held = newer.fire_held;
+        self.fire |= newer.fire;
+        self.hyperspace |= newer.hyperspace;
+        self.confirm |= newer.confirm;
+        self.escape |= newer.escape;
+        self.quit |= newer.quit;
+        self.reroll_seed |= newer.reroll_seed;
+        self.cycle_mode |= newer.cycle_mode;
+        self.cycle_destruction |= newer.cycle_destruction;
+        self.cycle_weapon |= newer.cycle_weapon;
+    }
+
+    // Called after a simulation step has seen the one-shot presses.
+    pub fn clear_presses(&mut self) {
+        self.fire = false;
+        self.hyperspace = false;
+        self.confirm = false;
+        self.escape = false;
+        self.quit = false;
+        self.reroll_seed = false;
+        self.cycle_mode = false;
+        self.cycle_destruction = false;
+        self.cycle_weapon = false;
+    }
+}
@@ -1,131 +1,207 @@
+use crate::game::components::asteroid::*;
+use crate::game::core::game_config::GameConfig;
 use crate::game::core::game_states::*;
-use crate::utils::screen_util::*;
+use crate::physics::collision::point_in_polygon;
+use crate::physics::world::World;
+use crate::render::renderer::Renderer;
+use crate::render::transform::Transform2D;
 use macroquad::prelude::*;
 
 const FONT_COLOR: Color = WHITE;
 const BACKGROUND_COLOR: Color = BLACK;
 const DEV_MODE: bool = false;
+const HUD_MARGIN: f32 = 10.0;
 
-fn draw_fps() {
-    if DEV_MODE {
-        draw_text(&format!("FPS: {:.2}", get_fps()), 10.0, 20.0, 20.0, WHITE);
-    }
-}
-
-pub fn render(game_state: &GameState) {
-    clear_background(BACKGROUND_COLOR);
-    draw_fps();
+// `alpha` is how far the frame is between the previous and current simulation step.
+// Only draws through `renderer`, so any frame can also be rendered offscreen.
+pub fn render(
+    renderer: &mut dyn Renderer,
+    game_state: &GameState,
+    config: &GameConfig,
+    alpha: f32,
+    world: &World,
+) {
+    renderer.clear(BACKGROUND_COLOR);
     match game_state {
         GameState::MainMenu => {
-            render_main_menu();
+            render_main_menu(renderer, config, world);
         }
         GameState::Playing { playing_info } => {
-            render_playing_info(playing_info);
-            playing_info.space_ship.render();
+            render_playing_info(renderer, playing_info, world);
+            playing_info.particles.render(renderer, alpha, world);
+            if !playing_info.space_ship.body.destroyed {
+                playing_info.space_ship.render(renderer, alpha, world);
+            }
             playing_info.bullets.iter().for_each(|bullet| {
-                bullet.render();
+                bullet.render(renderer, alpha, world);
             });
             playing_info.asteroids.iter().for_each(|asteroid| {
-                asteroid.render();
+                asteroid.render(renderer, alpha, world);
+            });
+            playing_info.saucers.iter().for_each(|saucer| {
+                saucer.render(renderer, alpha, world);
             });
+            playing_info.enemy_bullets.iter().for_each(|bullet| {
+                bullet.render(renderer, alpha, world);
+            });
+            if DEV_MODE {
+                render_grid_points(renderer, &playing_info.asteroids, world);
+            }
         }
-        GameState::NextLevel { level, score } => {
-            render_next_level(*level, *score);
+        GameState::NextLevel { level, score, .. } => {
+            render_next_level(renderer, *level, *score, world);
         }
         GameState::GameOver { level, score } => {
-            render_game_over(*level, *score);
+            render_game_over(renderer, *level, *score, world);
         }
+        GameState::Quit => {}
     }
+    renderer.flush();
 }
 
-fn render_main_menu() {
-    let center_x = get_center_x();
-    let center_y = get_center_y();
-    draw_text("Main Menu", center_x, center_y - 80.0, 30.0, FONT_COLOR);
-    draw_text(
-        "Press Enter to start",
-        center_x - 30.0,
-        center_y + 0.0,
-        20.0,
-        WHITE,
-    );
-    draw_text(
-        "Arrow keys or WASD to move",
-        center_x - 60.0,
-        center_y + 30.0,
-        20.0,
-        WHITE,
-    );
-    draw_text(
-        "Press q to quit",
-        center_x - 20.0,
-        center_y + 60.0,
-        20.0,
-        WHITE,
+fn render_main_menu(renderer: &mut dyn Renderer, config: &GameConfig, world: &World) {
+    let center = world.center();
+    draw_centered_text(
+        renderer,
+        "Main Menu",
+        center + vec2(0.0, -80.0),
+        30.0,
+        FONT_COLOR,
     );
+    let lines = [
+        "Press Enter to start".to_string(),
+        "Arrow keys or WASD to move".to_string(),
+        "Press q to quit".to_string(),
+        format!("Seed: {} (r to reroll)", config.seed),
+        format!("Mode: {} (m to change)", config.mode.name()),
+        format!("Asteroids: {} (f to change)", config.destruction.name()),
+        format!("Weapon: {} (e to change)", config.weapon.name()),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        draw_centered_text(
+            renderer,
+            line,
+            center + vec2(0.0, i as f32 * 30.0),
+            20.0,
+            FONT_COLOR,
+        );
+    }
 }
 
-fn render_playing_info(playing_info: &PlayingInfo) {
-    draw_text(
+fn render_playing_info(renderer: &mut dyn Renderer, playing_info: &PlayingInfo, world: &World) {
+    draw_centered_text(
+        renderer,
         &format!("Score: {}", playing_info.score),
-        get_center_x(),
-        20.0,
+        vec2(world.center().x, 30.0),
         30.0,
         FONT_COLOR,
     );
-    draw_text(
-        &format!("Level: {}", playing_info.level),
-        screen_width() - 150.0,
-        20.0,
+    // reserve ships as small copies of the ship, pointing up
+    let ship_shape = &playing_info.space_ship.ship_shape;
+    for i in 0..playing_info.lives {
+        let icon = Transform2D {
+            translation: vec2(HUD_MARGIN + 10.0 + i as f32 * 20.0, 20.0),
+            rotation: -std::f32::consts::FRAC_PI_2,
+            scale: Vec2::splat(0.7),
+        };
+        ship_shape.draw_with(renderer, &icon.matrix());
+    }
+    let level = format!("Level: {}", playing_info.level);
+    let level_width = renderer.text_width(&level, 30.0);
+    renderer.text(
+        &level,
+        vec2(world.width - level_width - HUD_MARGIN, 30.0),
         30.0,
         FONT_COLOR,
     );
+    let weapon = playing_info.space_ship.weapon.kind.name();
+    let weapon_width = renderer.text_width(weapon, 20.0);
+    renderer.text(
+        weapon,
+        vec2(world.width - weapon_width - HUD_MARGIN, 55.0),
+        20.0,
+        FONT_COLOR,
+    );
 }
 
-fn render_next_level(level: u32, score: u32) {
-    draw_text(
+fn render_next_level(renderer: &mut dyn Renderer, level: u32, score: u32, world: &World) {
+    let center = world.center();
+    draw_centered_text(
+        renderer,
         &format!("Level {} complete!", level),
-        get_center_x() - 60.0,
-        get_center_y(),
+        center,
         30.0,
         FONT_COLOR,
     );
-    draw_text(
+    draw_centered_text(
+        renderer,
         &format!("Score: {}", score),
-        get_center_x() - 0.0,
-        get_center_y() + 30.0,
+        center + vec2(0.0, 30.0),
         20.0,
         FONT_COLOR,
     );
-    draw_text(
+    draw_centered_text(
+        renderer,
         &format!("Press Enter to continue to level {}...", level + 1),
-        get_center_x() - 100.0,
-        get_center_y() + 60.0,
+        center + vec2(0.0, 60.0),
         20.0,
         FONT_COLOR,
     );
 }
 
-fn render_game_over(level: u32, score: u32) {
-    draw_text(
-        "Game Over",
-        get_center_x(),
-        get_center_y(),
-        30.0,
-        FONT_COLOR,
-    );
-    draw_text(
+fn render_game_over(renderer: &mut dyn Renderer, level: u32, score: u32, world: &World) {
+    let center = world.center();
+    draw_centered_text(renderer, "Game Over", center, 30.0, FONT_COLOR);
+    draw_centered_text(
+        renderer,
         &format!("Reached level {} with a score of {}", level, score),
-        get_center_x() - 100.0,
-        get_center_y() + 30.0,
+        center + vec2(0.0, 30.0),
         20.0,
         FONT_COLOR,
     );
-    draw_text(
+    draw_centered_text(
+        renderer,
         "Press Enter to return to main menu",
-        get_center_x() - 100.0,
-        get_center_y() + 60.0,
+        center + vec2(0.0, 60.0),
         20.0,
         FONT_COLOR,
     );
 }
+
+// `point` is the middle of the text's baseline.
+fn draw_centered_text(
+    renderer: &mut dyn Renderer,
+    text: &str,
+    point: Vec2,
+    font_size: f32,
+    color: Color,
+) {
+    let width = renderer.text_width(text, font_size);
+    renderer.text(text, point - vec2(width / 2.0, 0.0), font_size, color);
+}
+
+fn render_grid_points(renderer: &mut dyn Renderer, asteroids: &[Asteroid], world: &World) {
+    let step = 5;
+    let x_points = (0..(world.width as i32)).step_by(step);
+    let y_points = (0..(world.height as i32)).step_by(step);
+
+    for x in x_points {
+        for y in y_points.clone() {
+            let point = vec2(x as f32, y as f32);
+            let mut point_in_asteroid = false;
+
+            for asteroid in asteroids.iter() {
+                if point_in_polygon(&point, asteroid.shape.world()) {
+                    point_in_asteroid = true;
+                    break;
+                }
+            }
+
+            if point_in_asteroid {
+                renderer.circle(point, 2.0, RED);
+            } else {
+                // renderer.circle(point, 1.0, WHITE);
+            }
+        }
+    }
+}
@@ -1,196 +1,636 @@
 use crate::game::components::asteroid::*;
-use crate::game::components::bullet::*;
+use crate::game::components::particles::*;
+use crate::game::components::saucer::*;
 use crate::game::components::space_ship::*;
+use crate::game::components::weapon::*;
+use crate::game::core::game_config::*;
+use crate::game::core::game_input::InputFrame;
 use crate::game::core::game_states::*;
-use crate::physics::collision::point_in_polygon;
-use crate::utils::screen_util::*;
+use crate::physics::body::ScreenEdgeBehavior;
+use crate::physics::broadphase::*;
+use crate::physics::collision::{contact_manifold, ray_cast};
+use crate::physics::impulse::resolve_collision;
+use crate::physics::world::World;
+use crate::utils::game_rng::*;
 use macroquad::prelude::*;
 
-const LINEAR_ACCELERATION: f32 = 300.0; // pixels per second squared
-const ROTATIONAL_ACCELERATION: f32 = 0.10; // radians per second squared
-const BULLET_VEL: f32 = 500.0;
-const DEV_MODE: bool = false;
+// pixels per second squared
+const LINEAR_ACCELERATION: f32 = 300.0;
+// radians per second squared, with the ship's angular drag this tops out around 6 radians per second
+const ROTATIONAL_ACCELERATION: f32 = 48.0;
+const COLLISION_CELL_SIZE: f32 = 80.0; // about the size of a large asteroid
+const SHIP_BREAK_APART_TIME: f32 = 2.0; // seconds between losing a ship and the next one or game over
+const HIT_SPARKS: usize = 8;
+const STARTING_LIVES: u32 = 3;
+const EXTRA_LIFE_SCORE: u32 = 100; // another life every this many points
+const INVULNERABLE_TIME: f32 = 3.0; // seconds after respawning
+const SPAWN_CLEARANCE: f32 = 80.0; // free space around the center a ship needs to respawn
+const HYPERSPACE_MARGIN: f32 = 40.0; // jumps never land closer to an edge than this
+const LASER_COLOR: Color = RED;
 
-pub fn update_game_state(game_state: &mut GameState) {
+fn new_ship(config: &GameConfig, world: &World) -> SpaceShip {
+    let mut space_ship = SpaceShip::new(20.0, 20.0, world.center());
+    space_ship.body.screen_edge_behavior = config.mode.ship_edge_behavior();
+    space_ship.weapon = Weapon::new(config.weapon);
+    space_ship
+}
+
+fn new_level(level: u32, score: u32, lives: u32, config: &GameConfig, world: &World) -> GameState {
+    let rng = GameRng::for_level(config.seed, level);
+    let space_ship = new_ship(config, world);
+    let mut asteroids = create_asteroids(
+        level,
+        &SpawnRules::for_level(level),
+        space_ship.body.point,
+        world,
+        &rng.gameplay,
+    );
+    asteroids
+        .iter_mut()
+        .for_each(|a| a.body.screen_edge_behavior = config.mode.asteroid_edge_behavior());
+    GameState::Playing {
+        playing_info: Box::new(PlayingInfo {
+            score,
+            level,
+            lives,
+            next_extra_life: (score / EXTRA_LIFE_SCORE + 1) * EXTRA_LIFE_SCORE,
+            space_ship,
+            bullets: vec![],
+            asteroids,
+            saucers: vec![],
+            enemy_bullets: vec![],
+            saucer_in: get_saucer_interval(0.0),
+            level_time: 0.0,
+            particles: ParticleSystem::new(MAX_PARTICLES),
+            asteroid_grid: SpatialHash::new(world, COLLISION_CELL_SIZE),
+            candidates: vec![],
+            respawn_in: None,
+            rng,
+        }),
+    }
+}
+
+// Advances the game by `dt` seconds. Reads nothing from the window, so it can
+// be stepped headless with a hand built `InputFrame` and `World`.
+// The same seed and the same inputs always produce the same game.
+pub fn update_game_state(
+    game_state: &mut GameState,
+    config: &mut GameConfig,
+    input: &InputFrame,
+    dt: f32,
+    world: &World,
+) {
     let mut next_game_state: Option<GameState> = None;
 
     match game_state {
         GameState::MainMenu => {
-            let start = is_key_released(KeyCode::Enter);
-            let quit = is_key_released(KeyCode::Q);
-
-            if start {
-                next_game_state = Some(GameState::Playing {
-                    playing_info: PlayingInfo {
-                        score: 0,
-                        level: 1,
-                        space_ship: SpaceShip::new(
-                            20.0,
-                            20.0,
-                            vec2(get_center_x(), get_center_y()),
-                        ),
-                        bullets: vec![],
-                        asteroids: create_asteroids(1),
-                    },
-                });
+            if input.confirm {
+                next_game_state = Some(new_level(1, 0, STARTING_LIVES, config, world));
             }
-            if quit {
-                std::process::exit(0);
+            if input.reroll_seed {
+                config.seed = mix_seed(config.seed, 0);
+            }
+            if input.cycle_mode {
+                config.mode = config.mode.next();
+            }
+            if input.cycle_destruction {
+                config.destruction = config.destruction.next();
+            }
+            if input.cycle_weapon {
+                config.weapon = config.weapon.next();
+            }
+            if input.quit {
+                next_game_state = Some(GameState::Quit);
             }
         }
         GameState::Playing { playing_info } => {
-            let dt = get_frame_time();
-            let rotate_left = is_key_down(KeyCode::Left) || is_key_down(KeyCode::A);
-            let rotate_right = is_key_down(KeyCode::Right) || is_key_down(KeyCode::D);
-            let thrust_forward = is_key_down(KeyCode::Up) || is_key_down(KeyCode::W);
-            let fire = is_key_released(KeyCode::Space);
-            let escape = is_key_released(KeyCode::Escape);
+            playing_info.save_previous_states();
 
             let mut next_level: bool = false;
-            let mut rotation = 0.0;
+            let mut angular_acceleration = 0.0;
             let mut thrust = 0.0;
 
-            if rotate_left {
-                rotation += ROTATIONAL_ACCELERATION;
+            if input.rotate_left {
+                angular_acceleration += ROTATIONAL_ACCELERATION;
             }
-            if rotate_right {
-                rotation -= ROTATIONAL_ACCELERATION;
+            if input.rotate_right {
+                angular_acceleration -= ROTATIONAL_ACCELERATION;
             }
-            if thrust_forward {
+            if input.thrust_forward {
                 thrust += LINEAR_ACCELERATION;
             }
-            if fire {
-                playing_info.bullets.push(Bullet::new(
-                    playing_info.space_ship.body.point,
-                    playing_info.space_ship.body.rotation,
-                    BULLET_VEL,
-                ));
+            let ship_alive = !playing_info.space_ship.body.destroyed;
+            // between leaving and re-entering normal space the ship can't be steered, shoot or be hit
+            let ship_in_hyperspace = playing_info.space_ship.hyperspace.is_some();
+            if input.cycle_weapon {
+                config.weapon = config.weapon.next();
+                playing_info.space_ship.weapon = Weapon::new(config.weapon);
+            }
+            // resolved against the asteroids once they moved, like the bullets
+            let mut beam: Option<(Vec2, Vec2)> = None;
+            if (input.fire || input.fire_held)
+                && ship_alive
+                && !ship_in_hyperspace
+                && playing_info
+                    .space_ship
+                    .weapon
+                    .ready(playing_info.bullets.len())
+            {
+                match playing_info
+                    .space_ship
+                    .fire(config.mode.bullet_edge_behavior())
+                {
+                    Shot::Projectiles(mut bullets) => playing_info.bullets.append(&mut bullets),
+                    Shot::Beam { start, end } => beam = Some((start, end)),
+                }
+            }
+
+            if input.hyperspace && ship_alive && playing_info.space_ship.can_jump() {
+                let gameplay_rng = &playing_info.rng.gameplay;
+                let destination = vec2(
+                    gameplay_rng.gen_range(HYPERSPACE_MARGIN, world.width - HYPERSPACE_MARGIN),
+                    gameplay_rng.gen_range(HYPERSPACE_MARGIN, world.height - HYPERSPACE_MARGIN),
+                );
+                let fails = gameplay_rng.gen_range(0.0, 1.0) < config.hyperspace_failure_chance;
+                let space_ship = &mut playing_info.space_ship;
+                playing_info.particles.emit_sparks(
+                    space_ship.body.point,
+                    &space_ship.body,
+                    HIT_SPARKS,
+                    SKYBLUE,
+                    &playing_info.rng.cosmetic,
+                );
+                space_ship.start_hyperspace(destination, fails, config.hyperspace_cooldown);
+            }
+
+            if ship_alive {
+                let space_ship = &mut playing_info.space_ship;
+                space_ship.invulnerable_for = (space_ship.invulnerable_for - dt).max(0.0);
+                space_ship.hyperspace_cooldown = (space_ship.hyperspace_cooldown - dt).max(0.0);
+                space_ship.weapon.update(dt);
+                if space_ship.hyperspace.is_some() {
+                    if let Some(jump) = space_ship.update_hyperspace(dt) {
+                        if jump.fails {
+                            space_ship.body.destroyed = true;
+                        } else {
+                            playing_info.particles.emit_sparks(
+                                space_ship.body.point,
+                                &space_ship.body,
+                                HIT_SPARKS,
+                                SKYBLUE,
+                                &playing_info.rng.cosmetic,
+                            );
+                        }
+                    }
+                } else {
+                    let ship_body = &mut space_ship.body;
+                    ship_body.apply_torque(angular_acceleration * ship_body.moment_of_inertia);
+                    space_ship.apply_thrust(thrust);
+                    space_ship.body.update(dt, world);
+                    if space_ship.is_thrusting {
+                        playing_info.particles.emit_exhaust(
+                            space_ship.nozzle(),
+                            &space_ship.body,
+                            &playing_info.rng.cosmetic,
+                        );
+                    }
+                }
             }
 
-            playing_info.space_ship.body.rotate(rotation);
-            playing_info.space_ship.apply_thrust(thrust);
-            playing_info.space_ship.body.update(dt);
+            playing_info.level_time += dt;
+            if playing_info.saucers.is_empty() {
+                playing_info.saucer_in -= dt;
+                if playing_info.saucer_in <= 0.0 {
+                    let mut saucer =
+                        create_saucer(playing_info.level, world, &playing_info.rng.gameplay);
+                    saucer.body.screen_edge_behavior = config.mode.saucer_edge_behavior();
+                    playing_info.saucers.push(saucer);
+                    playing_info.saucer_in = get_saucer_interval(playing_info.level_time);
+                }
+            }
+            let space_ship = &playing_info.space_ship;
+            let target = (!space_ship.body.destroyed && space_ship.hyperspace.is_none())
+                .then_some(&space_ship.body);
+            for saucer in playing_info.saucers.iter_mut() {
+                if let Some(mut bullet) =
+                    saucer.update(dt, world, target, &playing_info.rng.gameplay)
+                {
+                    bullet.body.screen_edge_behavior = config.mode.bullet_edge_behavior();
+                    playing_info.enemy_bullets.push(bullet);
+                }
+            }
 
+            for b in playing_info
+                .bullets
+                .iter_mut()
+                .filter(|b| b.homing_turn_rate > 0.0)
+            {
+                // the nearest asteroid or saucer, counting wrapped copies
+                let nearest = playing_info
+                    .asteroids
+                    .iter()
+                    .map(|a| &a.body)
+                    .chain(playing_info.saucers.iter().map(|s| &s.body))
+                    .filter(|body| !body.destroyed)
+                    .map(|body| world.wrapped_delta(b.body.point, body.point))
+                    .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+                if let Some(to_target) = nearest {
+                    b.steer_towards(to_target, dt);
+                }
+            }
             playing_info
                 .bullets
                 .iter_mut()
-                .for_each(|b| b.body.update(dt));
+                .chain(playing_info.enemy_bullets.iter_mut())
+                .for_each(|b| b.body.update(dt, world));
 
             playing_info
                 .asteroids
                 .iter_mut()
-                .for_each(|a| a.body.update(dt));
+                .for_each(|a| a.body.update(dt, world));
+            playing_info.particles.update(dt, world);
+            playing_info.update_transforms();
+
+            // broadphase: bucket asteroids by grid cell so bullets and the ship
+            // only run the polygon test against asteroids near them
+            let asteroid_grid = &mut playing_info.asteroid_grid;
+            asteroid_grid.clear();
+            playing_info
+                .asteroids
+                .iter()
+                .enumerate()
+                .for_each(|(i, a)| {
+                    asteroid_grid.insert(i, &Aabb::from_circle(&a.bounding_circle()))
+                });
+            let candidates = &mut playing_info.candidates;
+
+            if config.mode.asteroid_collisions() {
+                resolve_asteroid_collisions(
+                    &mut playing_info.asteroids,
+                    asteroid_grid,
+                    candidates,
+                    world,
+                );
+                // bounces moved them
+                playing_info
+                    .asteroids
+                    .iter_mut()
+                    .for_each(|a| a.update_transforms());
+            }
 
             let mut new_asteroids: Vec<Asteroid> = vec![];
-            playing_info.asteroids.iter_mut().for_each(|a| {
-                playing_info.bullets.iter_mut().for_each(|b| {
-                    if a.shape.collides_with(
-                        a.body.point,
-                        a.body.rotation,
-                        &b.shape,
-                        b.body.point,
-                        b.body.rotation,
-                    ) {
+            // saucer bullets break asteroids too, but only the player scores
+            let all_bullets = playing_info
+                .bullets
+                .iter_mut()
+                .map(|b| (b, true))
+                .chain(playing_info.enemy_bullets.iter_mut().map(|b| (b, false)));
+            for (b, fired_by_player) in all_bullets {
+                asteroid_grid.query(&b.bounds(), candidates);
+                for &i in candidates.iter() {
+                    let a = &mut playing_info.asteroids[i];
+                    if a.body.destroyed || b.body.destroyed {
+                        continue;
+                    }
+                    // move the bullet next to the asteroid rather than the other way
+                    // around, it's only two points
+                    let image_offset = a.body.image_near(b.body.point, world) - a.body.point;
+                    if b.hits(a.shape.world(), -image_offset) {
                         a.body.destroyed = true;
                         b.body.destroyed = true;
+                        if fired_by_player {
+                            playing_info.score += get_asteroid_score(&a.size);
+                        }
+                        // the bullet's line, next to this copy of the asteroid
+                        let mut pieces = break_asteroid(
+                            a,
+                            b.body.point - image_offset,
+                            b.body.velocity,
+                            config,
+                            &mut playing_info.particles,
+                            &playing_info.rng,
+                        );
+                        new_asteroids.append(&mut pieces);
+                    }
+                }
+            }
+
+            // the laser goes through everything in its way
+            if let Some((start, mut end)) = beam {
+                if !matches!(config.mode.bullet_edge_behavior(), ScreenEdgeBehavior::Wrap) {
+                    end = start + (end - start) * beam_inside_world(start, end, world);
+                }
+                let direction = (end - start).normalize_or_zero();
+                let length = start.distance(end);
+                // copies of a target are a world apart, and the beam together with a
+                // target's width fits inside the world, so the copy nearest the
+                // beam's middle is the only one it can reach
+                let middle = (start + end) / 2.0;
+                asteroid_grid.query(&Aabb::from_points(&[start, end]), candidates);
+                for &i in candidates.iter() {
+                    let a = &mut playing_info.asteroids[i];
+                    if a.body.destroyed {
+                        continue;
+                    }
+                    let image_offset = a.body.image_near(middle, world) - a.body.point;
+                    let origin = start - image_offset;
+                    if let Some(distance) = ray_cast(origin, direction, length, a.shape.world()) {
+                        a.body.destroyed = true;
                         playing_info.score += get_asteroid_score(&a.size);
-                        new_asteroids.append(&mut split_asteroid(a));
+                        let mut pieces = break_asteroid(
+                            a,
+                            origin + direction * distance,
+                            direction,
+                            config,
+                            &mut playing_info.particles,
+                            &playing_info.rng,
+                        );
+                        new_asteroids.append(&mut pieces);
                     }
-                });
-                if a.shape.collides_with(
-                    a.body.point,
-                    a.body.rotation,
-                    &playing_info.space_ship.ship_shape,
-                    playing_info.space_ship.body.point,
-                    playing_info.space_ship.body.rotation,
-                ) {
+                }
+                for s in playing_info.saucers.iter_mut() {
+                    let image_offset = s.body.image_near(middle, world) - s.body.point;
+                    if !s.body.destroyed
+                        && ray_cast(start - image_offset, direction, length, s.shape.world())
+                            .is_some()
+                    {
+                        s.body.destroyed = true;
+                        playing_info.score += get_saucer_score(&s.size);
+                    }
+                }
+                playing_info.particles.emit_beam(
+                    start,
+                    end,
+                    LASER_COLOR,
+                    config.mode.bullet_edge_behavior(),
+                );
+            }
+
+            for b in playing_info.bullets.iter_mut() {
+                for s in playing_info.saucers.iter_mut() {
+                    if s.body.destroyed || b.body.destroyed {
+                        continue;
+                    }
+                    let image_offset = s.body.image_near(b.body.point, world) - s.body.point;
+                    if b.hits(s.shape.world(), -image_offset) {
+                        s.body.destroyed = true;
+                        b.body.destroyed = true;
+                        playing_info.score += get_saucer_score(&s.size);
+                    }
+                }
+            }
+            // saucers don't dodge asteroids
+            for s in playing_info.saucers.iter_mut() {
+                asteroid_grid.query(&Aabb::from_circle(&s.bounding_circle()), candidates);
+                for &i in candidates.iter() {
+                    let a = &playing_info.asteroids[i];
+                    let offset = a.body.image_near(s.body.point, world) - a.body.point;
+                    if !a.body.destroyed && a.shape.collides_with(offset, &s.shape) {
+                        s.body.destroyed = true;
+                    }
+                }
+            }
+
+            let space_ship = &mut playing_info.space_ship;
+            let ship_vulnerable = !space_ship.body.destroyed
+                && !space_ship.is_invulnerable()
+                && space_ship.hyperspace.is_none();
+            if ship_vulnerable {
+                for b in playing_info.enemy_bullets.iter_mut() {
+                    let image_offset =
+                        space_ship.body.image_near(b.body.point, world) - space_ship.body.point;
+                    if b.hits(space_ship.ship_shape.world(), -image_offset) {
+                        b.body.destroyed = true;
+                        space_ship.body.destroyed = true;
+                    }
+                }
+                for s in playing_info.saucers.iter_mut() {
+                    let offset = s.body.image_near(space_ship.body.point, world) - s.body.point;
+                    if !s.body.destroyed && s.shape.collides_with(offset, &space_ship.ship_shape) {
+                        s.body.destroyed = true;
+                        space_ship.body.destroyed = true;
+                        playing_info.score += get_saucer_score(&s.size);
+                    }
+                }
+            }
+            for s in playing_info.saucers.iter().filter(|s| s.body.destroyed) {
+                // saucers that simply flew off the screen are out of sight already
+                if s.horizontal_travelled < s.course_length(world) {
+                    playing_info.particles.emit_debris(
+                        s.shape.world(),
+                        s.shape.color,
+                        &s.body,
+                        &playing_info.rng.cosmetic,
+                    );
+                }
+            }
+
+            let ship_bounds = playing_info.space_ship.bounding_circle();
+            if ship_vulnerable {
+                asteroid_grid.query(&Aabb::from_circle(&ship_bounds), candidates);
+            } else {
+                candidates.clear();
+            }
+            for &i in candidates.iter() {
+                let a = &playing_info.asteroids[i];
+                let asteroid_bounds = BoundingCircle {
+                    center: a.body.image_near(ship_bounds.center, world),
+                    ..a.bounding_circle()
+                };
+                if asteroid_bounds.overlaps(&ship_bounds)
+                    && a.shape.collides_with(
+                        asteroid_bounds.center - a.body.point,
+                        &playing_info.space_ship.ship_shape,
+                    )
+                {
                     playing_info.space_ship.body.destroyed = true;
                 }
-            });
+            }
+            if ship_alive && playing_info.space_ship.body.destroyed {
+                let space_ship = &mut playing_info.space_ship;
+                space_ship.apply_thrust(0.0);
+                playing_info.particles.emit_debris(
+                    space_ship.ship_shape.world(),
+                    space_ship.ship_shape.color,
+                    &space_ship.body,
+                    &playing_info.rng.cosmetic,
+                );
+                playing_info.particles.emit_sparks(
+                    space_ship.body.point,
+                    &space_ship.body,
+                    HIT_SPARKS * 2,
+                    YELLOW,
+                    &playing_info.rng.cosmetic,
+                );
+                playing_info.respawn_in = Some(SHIP_BREAK_APART_TIME);
+            }
+
+            while playing_info.score >= playing_info.next_extra_life {
+                playing_info.lives += 1;
+                playing_info.next_extra_life += EXTRA_LIFE_SCORE;
+            }
 
-            if new_asteroids.len() > 0 {
+            if !new_asteroids.is_empty() {
                 playing_info.asteroids.append(&mut new_asteroids);
             }
             playing_info.bullets.retain(|b| !b.body.destroyed); // remove destroyed bullets
+            playing_info.enemy_bullets.retain(|b| !b.body.destroyed);
+            playing_info.saucers.retain(|s| !s.body.destroyed);
             playing_info.asteroids.retain(|a| !a.body.destroyed); // remove destroyed asteroids
 
-            if playing_info.asteroids.is_empty() {
+            if playing_info.asteroids.is_empty() && !playing_info.space_ship.body.destroyed {
                 next_level = true;
             }
 
-            if DEV_MODE {
-                render_grid_points(&playing_info.asteroids);
-            }
-
-            if playing_info.space_ship.body.destroyed {
-                next_game_state = Some(GameState::GameOver {
-                    level: playing_info.level,
-                    score: playing_info.score,
-                });
+            if let Some(respawn_in) = playing_info.respawn_in.as_mut() {
+                *respawn_in -= dt;
+                if *respawn_in <= 0.0 {
+                    if playing_info.lives == 0 {
+                        next_game_state = Some(GameState::GameOver {
+                            level: playing_info.level,
+                            score: playing_info.score,
+                        });
+                    } else if spawn_area_clear(&playing_info.asteroids, world) {
+                        playing_info.lives -= 1;
+                        playing_info.space_ship = new_ship(config, world);
+                        playing_info.space_ship.invulnerable_for = INVULNERABLE_TIME;
+                        playing_info.respawn_in = None;
+                    }
+                }
             }
-            if escape {
+            if input.escape {
                 next_game_state = Some(GameState::MainMenu);
             }
             if next_level {
                 next_game_state = Some(GameState::NextLevel {
                     level: playing_info.level,
                     score: playing_info.score,
+                    lives: playing_info.lives,
                 });
             }
         }
-        GameState::NextLevel { level, score } => {
-            if is_key_released(KeyCode::Enter) {
-                let next_level = *level + 1;
-                next_game_state = Some(GameState::Playing {
-                    playing_info: PlayingInfo {
-                        score: *score,
-                        level: next_level,
-                        space_ship: SpaceShip::new(
-                            20.0,
-                            20.0,
-                            vec2(get_center_x(), get_center_y()),
-                        ),
-                        bullets: vec![],
-                        asteroids: create_asteroids(next_level),
-                    },
-                });
+        GameState::NextLevel {
+            level,
+            score,
+            lives,
+        } => {
+            if input.confirm {
+                next_game_state = Some(new_level(*level + 1, *score, *lives, config, world));
             }
         }
         GameState::GameOver { .. } => {
-            if is_key_released(KeyCode::Enter) {
+            if input.confirm {
                 next_game_state = Some(GameState::MainMenu);
             }
         }
+        GameState::Quit => {}
     }
     if let Some(next_state) = next_game_state {
         *game_state = next_state;
     }
 }
 
-fn render_grid_points(asteroids: &Vec<Asteroid>) {
-    let step = 5;
-    let x_points = (0..(screen_width() as i32)).step_by(step);
-    let y_points = (0..(screen_height() as i32)).step_by(step);
+// Breaks up an asteroid hit at `point`, next to the asteroid rather than across
+// a wrapped edge, by something moving along `direction`. Returns the pieces.
+fn break_asteroid(
+    asteroid: &Asteroid,
+    point: Vec2,
+    direction: Vec2,
+    config: &GameConfig,
+    particles: &mut ParticleSystem,
+    rng: &GameRng,
+) -> Vec<Asteroid> {
+    particles.emit_sparks(point, &asteroid.body, HIT_SPARKS, WHITE, &rng.cosmetic);
+    match config.destruction {
+        DestructionModel::Split => {
+            let pieces = split_asteroid(asteroid, &rng.gameplay);
+            if pieces.is_empty() {
+                particles.emit_debris(
+                    asteroid.shape.world(),
+                    asteroid.shape.color,
+                    &asteroid.body,
+                    &rng.cosmetic,
+                );
+            }
+            pieces
+        }
+        DestructionModel::Fracture => {
+            let fracture = fracture_asteroid(asteroid, point, direction);
+            for dust in fracture.dust.iter() {
+                particles.emit_debris(dust, asteroid.shape.color, &asteroid.body, &rng.cosmetic);
+            }
+            fracture.fragments
+        }
+    }
+}
 
-    for x in x_points {
-        for y in y_points.clone() {
-            let point = vec2(x as f32, y as f32);
-            let mut point_in_asteroid = false;
+// How much of the beam from `start` to `end` is left once it hits the world's edge, 0 to 1.
+fn beam_inside_world(start: Vec2, end: Vec2, world: &World) -> f32 {
+    let delta = end - start;
+    let exit = |from: f32, delta: f32, size: f32| {
+        if delta > 0.0 {
+            (size - from) / delta
+        } else if delta < 0.0 {
+            -from / delta
+        } else {
+            1.0
+        }
+    };
+    exit(start.x, delta.x, world.width)
+        .min(exit(start.y, delta.y, world.height))
+        .clamp(0.0, 1.0)
+}
 
-            for asteroid in asteroids.iter() {
-                let transformed_points = asteroid
-                    .shape
-                    .transform(asteroid.body.point, asteroid.body.rotation);
-                if point_in_polygon(&point, &transformed_points) {
-                    point_in_asteroid = true;
-                    break;
-                }
-            }
+// True if no asteroid is within `SPAWN_CLEARANCE` of the center, counting wrapped copies.
+fn spawn_area_clear(asteroids: &[Asteroid], world: &World) -> bool {
+    let center = world.center();
+    asteroids.iter().all(|a| {
+        world.wrapped_delta(center, a.body.point).length()
+            > SPAWN_CLEARANCE + a.shape.bounding_radius()
+    })
+}
 
-            if point_in_asteroid {
-                draw_circle(point.x, point.y, 2.0, RED);
+fn resolve_asteroid_collisions(
+    asteroids: &mut [Asteroid],
+    asteroid_grid: &SpatialHash,
+    candidates: &mut Vec<usize>,
+    world: &World,
+) {
+    for i in 0..asteroids.len() {
+        asteroid_grid.query(
+            &Aabb::from_circle(&asteroids[i].bounding_circle()),
+            candidates,
+        );
+        // each pair once, the lower index resolves it
+        for &j in candidates.iter().filter(|&&j| j > i) {
+            let (head, tail) = asteroids.split_at_mut(j);
+            let (a, b) = (&mut head[i], &mut tail[0]);
+            // compare against the copy of b on a's side of any wrapped edge
+            let b_bounds = BoundingCircle {
+                center: b.body.image_near(a.body.point, world),
+                ..b.bounding_circle()
+            };
+            if !a.bounding_circle().overlaps(&b_bounds) {
+                continue;
+            }
+            let b_offset = b_bounds.center - b.body.point;
+            let manifold = if b_offset == Vec2::ZERO {
+                contact_manifold(
+                    a.shape.world(),
+                    a.body.velocity,
+                    b.shape.world(),
+                    b.body.velocity,
+                )
             } else {
-                // draw_circle(point.x, point.y, 1.0, WHITE);
+                contact_manifold(
+                    a.shape.world(),
+                    a.body.velocity,
+                    &b.shape.world().map(|point| point + b_offset),
+                    b.body.velocity,
+                )
+            };
+            if let Some(manifold) = manifold {
+                resolve_collision(&mut a.body, &mut b.body, &manifold);
             }
         }
     }
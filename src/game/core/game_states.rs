@@ -1,19 +1,70 @@
 use crate::game::components::asteroid::*;
 use crate::game::components::bullet::*;
+use crate::game::components::particles::*;
+use crate::game::components::saucer::*;
 use crate::game::components::space_ship::*;
-use macroquad::prelude::*;
+use crate::physics::broadphase::SpatialHash;
+use crate::utils::game_rng::GameRng;
 
 pub struct PlayingInfo {
     pub score: u32,
     pub level: u32,
+    // ships in reserve, not counting the one in play
+    pub lives: u32,
+    // score at which another life is awarded
+    pub next_extra_life: u32,
     pub space_ship: SpaceShip,
     pub bullets: Vec<Bullet>,
     pub asteroids: Vec<Asteroid>,
+    pub saucers: Vec<Saucer>,
+    // fired by saucers, they hit the ship and asteroids but not saucers
+    pub enemy_bullets: Vec<Bullet>,
+    // seconds until the next saucer, counted while none is flying
+    pub saucer_in: f32,
+    // seconds since the level started
+    pub level_time: f32,
+    pub particles: ParticleSystem,
+    // rebuilt every step, kept so its cells and the query buffer are reused
+    pub asteroid_grid: SpatialHash,
+    pub candidates: Vec<usize>,
+    // seconds left to watch the ship break apart, then the next ship spawns
+    // once the center is clear, or the game is over if there are no lives left
+    pub respawn_in: Option<f32>,
+    pub rng: GameRng,
+}
+
+impl PlayingInfo {
+    // Recomputes every world space shape after the bodies moved, once per step.
+    pub fn update_transforms(&mut self) {
+        self.space_ship.update_transforms();
+        self.bullets.iter_mut().for_each(|b| b.update_transforms());
+        self.asteroids
+            .iter_mut()
+            .for_each(|a| a.update_transforms());
+        self.saucers.iter_mut().for_each(|s| s.update_transforms());
+        self.enemy_bullets
+            .iter_mut()
+            .for_each(|b| b.update_transforms());
+    }
+
+    pub fn save_previous_states(&mut self) {
+        self.space_ship.body.save_previous();
+        self.bullets.iter_mut().for_each(|b| b.body.save_previous());
+        self.asteroids
+            .iter_mut()
+            .for_each(|a| a.body.save_previous());
+        self.saucers.iter_mut().for_each(|s| s.body.save_previous());
+        self.enemy_bullets
+            .iter_mut()
+            .for_each(|b| b.body.save_previous());
+        self.particles.save_previous();
+    }
 }
 
 pub enum GameState {
     MainMenu,
-    Playing { playing_info: PlayingInfo },
-    NextLevel { level: u32, score: u32 },
+    Playing { playing_info: Box<PlayingInfo> },
+    NextLevel { level: u32, score: u32, lives: u32 },
     GameOver { level: u32, score: u32 },
+    Quit,
 }
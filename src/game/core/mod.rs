@@ -1,3 +1,5 @@
+pub mod game_config;
+pub mod game_input;
 pub mod game_render;
 pub mod game_state_machine;
 pub mod game_states;
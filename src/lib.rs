@@ -0,0 +1,4 @@
+pub mod game;
+pub mod physics;
+pub mod render;
+pub mod utils;
@@ -1,43 +1,110 @@
-mod game;
-mod physics;
-mod render;
-mod utils;
-
-use std::thread::sleep;
-
+use asteroids::game;
+use asteroids::game::core::game_config::GameConfig;
+use asteroids::game::core::game_input::InputFrame;
+use asteroids::game::core::game_states::GameState;
+use asteroids::physics::world::World;
+use asteroids::render::macroquad_renderer::MacroquadRenderer;
+use asteroids::render::svg_renderer::SvgRenderer;
+use asteroids::utils::fixed_timestep::FixedTimestep;
+use asteroids::utils::screen_util::Viewport;
 use macroquad::prelude::*;
 
-const TARGET_FPS: f64 = 60.0;
+const SIMULATION_HZ: f32 = 120.0;
 const DEV_MODE: bool = false;
+// the play area in world units, however big the window is
+const WORLD_WIDTH: f32 = 800.0;
+const WORLD_HEIGHT: f32 = 600.0;
+
+fn window_conf() -> Conf {
+    Conf {
+        window_title: "Asteroids".to_string(),
+        window_width: WORLD_WIDTH as i32,
+        window_height: WORLD_HEIGHT as i32,
+        window_resizable: true,
+        ..Default::default()
+    }
+}
 
-#[macroquad::main("Asteroids")]
+#[macroquad::main(window_conf)]
 async fn main() {
-    let mut game_state = game::core::game_states::GameState::MainMenu;
+    let args: Vec<String> = std::env::args().collect();
+    let mut config = GameConfig::from_args(&args, miniquad::date::now().to_bits());
+    let mut game_state = GameState::MainMenu;
+    let mut timestep = FixedTimestep::new(SIMULATION_HZ);
+    let mut input = InputFrame::default();
+    let mut renderer = MacroquadRenderer::new();
+    let mut frame: u64 = 0;
+    let world = World::new(WORLD_WIDTH, WORLD_HEIGHT);
     loop {
         let start_time = get_time();
-        game::core::game_state_machine::update_game_state(&mut game_state);
+        input.merge(&InputFrame::poll());
+        timestep.advance(get_frame_time());
+        let mut steps = 0;
+        while timestep.consume_step() {
+            game::core::game_state_machine::update_game_state(
+                &mut game_state,
+                &mut config,
+                &input,
+                timestep.step,
+                &world,
+            );
+            input.clear_presses();
+            steps += 1;
+        }
+        if let GameState::Quit = game_state {
+            break;
+        }
         let game_state_duration = get_time() - start_time;
         let render_start_time = get_time();
-        game::core::game_render::render(&game_state);
+        // recomputed every frame, so resizing the window only changes how big
+        // the world is drawn and never the world itself
+        let viewport = Viewport::letterbox(&world, screen_width(), screen_height());
+        set_camera(&viewport.camera(&world));
+        renderer.text_scale = viewport.scale(&world);
+        game::core::game_render::render(
+            &mut renderer,
+            &game_state,
+            &config,
+            timestep.alpha(),
+            &world,
+        );
+        set_default_camera();
         let render_duration = get_time() - render_start_time;
 
-        let elapsed_time = get_time() - start_time;
-        let target_frame_time = 1.0 / TARGET_FPS;
-        let sleep_duration = target_frame_time - elapsed_time;
+        // F12 saves what's on screen as an SVG in the working directory
+        if is_key_pressed(KeyCode::F12) {
+            let mut svg = SvgRenderer::new(world.width, world.height);
+            game::core::game_render::render(
+                &mut svg,
+                &game_state,
+                &config,
+                timestep.alpha(),
+                &world,
+            );
+            let path = format!("frame_{}.svg", frame);
+            match svg.save(&path) {
+                Ok(()) => println!("Saved {}", path),
+                Err(error) => eprintln!("Could not save {}: {}", path, error),
+            }
+        }
 
         if DEV_MODE {
+            draw_text(
+                format!("FPS: {:.2}", get_fps()).as_str(),
+                10.0,
+                20.0,
+                20.0,
+                WHITE,
+            );
             println!(
-            "Game state duration: {:.2} us, Render duration: {:.2} us, Elapsed time: {:.2} us, Target frame time: {:.2} us",
-            game_state_duration * 1000000.0,
-            render_duration * 1000000.0,
-            elapsed_time * 1000000.0,
-            target_frame_time * 1000000.0,
-        );
+                "Simulation steps: {}, Game state duration: {:.2} us, Render duration: {:.2} us",
+                steps,
+                game_state_duration * 1000000.0,
+                render_duration * 1000000.0,
+            );
         }
 
-        if sleep_duration > 0.0 {
-            sleep(std::time::Duration::from_secs_f64(sleep_duration));
-        }
+        frame += 1;
         next_frame().await;
     }
 }
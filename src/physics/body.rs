@@ -1,9 +1,15 @@
+use crate::physics::integrator::Integrator;
+use crate::physics::world::World;
 use macroquad::prelude::*;
 
 #[derive(Debug, Clone)]
 pub enum ScreenEdgeBehavior {
     Wrap,
     Destroy,
+    // reflect off the edges, keeping `wall_restitution` of the speed
+    Bounce,
+    // stop at the edges
+    Clamp,
 }
 
 #[derive(Debug, Clone)]
@@ -11,62 +17,316 @@ pub struct Body {
     pub point: Vec2,
     pub velocity: Vec2,
     pub acceleration: Vec2,
+    // orientation the body is drawn with and thrusts along, independent of the
+    // direction it travels in
     pub rotation: f32,
-    pub drag_coefficient: f32,
+    pub angular_velocity: f32,     // radians per second
+    pub angular_acceleration: f32, // radians per second squared, reset every update
+    pub angular_drag: f32,         // exponential decay rate of the angular velocity, per second
+    pub moment_of_inertia: f32,
+    pub linear_drag: f32, // exponential decay rate of the velocity, per second
+    pub integrator: Integrator,
+    // integrator steps per update, raise for stiff forces like close gravity wells
+    pub substeps: u32,
+    pub mass: f32,
+    // share of the approach speed kept after a bounce, 0 sticks, 1 is perfectly elastic
+    pub restitution: f32,
+    // the same for bouncing off the screen edges, separate so bodies that lose
+    // speed to each other don't also grind down against the walls
+    pub wall_restitution: f32,
     pub screen_edge_behavior: ScreenEdgeBehavior,
+    // how far the body's shape reaches from `point`, bouncing and clamping keep
+    // it this far from the edges so walls are solid for the whole shape
+    pub extent: f32,
+    // destroyed once alive longer than `lifetime` seconds or moved further than
+    // `range`, independent of the edge behavior
+    pub lifetime: Option<f32>,
+    pub range: Option<f32>,
+    pub age: f32,
+    pub distance_travelled: f32,
     pub destroyed: bool,
+    // state at the start of the current simulation step, used to interpolate rendering
+    pub previous_point: Vec2,
+    pub previous_rotation: f32,
 }
 
 impl Body {
+    pub fn new(
+        point: Vec2,
+        rotation: f32,
+        velocity: Vec2,
+        screen_edge_behavior: ScreenEdgeBehavior,
+    ) -> Body {
+        Body {
+            point,
+            velocity,
+            acceleration: vec2(0.0, 0.0),
+            rotation,
+            angular_velocity: 0.0,
+            angular_acceleration: 0.0,
+            angular_drag: 0.0,
+            moment_of_inertia: 1.0,
+            linear_drag: 0.0,
+            integrator: Integrator::SemiImplicitEuler,
+            substeps: 1,
+            mass: 1.0,
+            restitution: 1.0,
+            wall_restitution: 1.0,
+            screen_edge_behavior,
+            extent: 0.0,
+            lifetime: None,
+            range: None,
+            age: 0.0,
+            distance_travelled: 0.0,
+            destroyed: false,
+            previous_point: point,
+            previous_rotation: rotation,
+        }
+    }
+
+    pub fn inverse_mass(&self) -> f32 {
+        if self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            // zero mass is treated as immovable
+            0.0
+        }
+    }
+
+    pub fn momentum(&self) -> Vec2 {
+        self.velocity * self.mass
+    }
+
+    // How fast the part of the body at `point` moves, spin included.
+    pub fn velocity_at(&self, point: Vec2) -> Vec2 {
+        // a point on a spinning body moves at angular velocity x radius
+        self.velocity + (point - self.point).perp() * self.angular_velocity
+    }
+
     pub fn apply_thrust(&mut self, thrust: f32) {
         self.acceleration.x += thrust * self.rotation.cos();
         self.acceleration.y += thrust * self.rotation.sin();
     }
 
-    pub fn rotate(&mut self, rotation: f32) {
-        self.rotation = (self.rotation + rotation) % (2.0 * std::f32::consts::PI);
+    pub fn apply_torque(&mut self, torque: f32) {
+        if self.moment_of_inertia > 0.0 {
+            self.angular_acceleration += torque / self.moment_of_inertia;
+        }
     }
 
-    pub fn drag(&mut self) {
-        self.acceleration *= 0.0;
-        self.velocity *= self.drag_coefficient;
+    fn spin(&mut self, dt: f32) {
+        self.angular_velocity += self.angular_acceleration * dt;
+        self.angular_velocity *= (-self.angular_drag * dt).exp();
+        self.rotation =
+            (self.rotation + self.angular_velocity * dt).rem_euclid(2.0 * std::f32::consts::PI);
+        self.angular_acceleration = 0.0;
     }
 
-    pub fn update(&mut self, dt: f32) {
-        self.velocity += self.acceleration * dt;
-        self.point += self.velocity * dt;
-        self.drag();
+    // Must be called once before every simulation step, before any forces are applied.
+    pub fn save_previous(&mut self) {
+        self.previous_point = self.point;
+        self.previous_rotation = self.rotation;
+    }
 
+    // Pose between the previous and current step, `alpha` in 0..1.
+    pub fn interpolated(&self, alpha: f32) -> (Vec2, f32) {
+        let point = self.previous_point.lerp(self.point, alpha);
+        let mut rotation_delta = self.rotation - self.previous_rotation;
+        // take the short way around when the rotation wraps past 2 pi
+        if rotation_delta > std::f32::consts::PI {
+            rotation_delta -= 2.0 * std::f32::consts::PI;
+        } else if rotation_delta < -std::f32::consts::PI {
+            rotation_delta += 2.0 * std::f32::consts::PI;
+        }
+        (point, self.previous_rotation + rotation_delta * alpha)
+    }
+
+    // Moves points placed at the current pose to the interpolated one, turning
+    // them about the body's point. For drawing the shapes cached each step.
+    pub fn interpolation_transform(&self, alpha: f32) -> Mat3 {
+        let (point, rotation) = self.interpolated(alpha);
+        Mat3::from_translation(point)
+            * Mat3::from_angle(rotation - self.rotation)
+            * Mat3::from_translation(-self.point)
+    }
+
+    // Offsets to draw the body at, see `World::wrap_offsets`. Only wrapping
+    // bodies show up across the edges.
+    pub fn wrap_offsets(&self, point: Vec2, radius: f32, world: &World) -> Vec<Vec2> {
+        match self.screen_edge_behavior {
+            ScreenEdgeBehavior::Wrap => world.wrap_offsets(point, radius),
+            _ => vec![Vec2::ZERO],
+        }
+    }
+
+    // Position of the copy of the body closest to `reference`. Collision tests
+    // use it so objects touching across a wrapped edge still hit each other.
+    pub fn image_near(&self, reference: Vec2, world: &World) -> Vec2 {
         match self.screen_edge_behavior {
-            ScreenEdgeBehavior::Wrap => self.warp_around(),
-            ScreenEdgeBehavior::Destroy => self.destroy(),
+            ScreenEdgeBehavior::Wrap => reference + world.wrapped_delta(reference, self.point),
+            _ => self.point,
         }
     }
 
-    fn warp_around(&mut self) {
-        let screen_width = screen_width();
-        let screen_height = screen_height();
-        if self.point.x > screen_width {
+    pub fn update(&mut self, dt: f32, world: &World) {
+        self.update_in_field(dt, world, &|_| Vec2::ZERO);
+    }
+
+    // Like `update`, with an extra position dependent acceleration such as gravity.
+    pub fn update_in_field(&mut self, dt: f32, world: &World, field: &dyn Fn(Vec2) -> Vec2) {
+        let applied_acceleration = self.acceleration;
+        let acceleration = |point: Vec2| applied_acceleration + field(point);
+        let start_point = self.point;
+        let substeps = self.substeps.max(1);
+        let substep_dt = dt / substeps as f32;
+        for _ in 0..substeps {
+            self.integrator.integrate(
+                &mut self.point,
+                &mut self.velocity,
+                self.linear_drag,
+                substep_dt,
+                &acceleration,
+            );
+        }
+        self.acceleration = Vec2::ZERO;
+        self.spin(dt);
+        self.expire(dt, start_point.distance(self.point));
+
+        match self.screen_edge_behavior {
+            ScreenEdgeBehavior::Wrap => self.warp_around(world),
+            ScreenEdgeBehavior::Destroy => self.destroy(world),
+            ScreenEdgeBehavior::Bounce => self.bounce(world),
+            ScreenEdgeBehavior::Clamp => self.clamp(world),
+        }
+    }
+
+    fn expire(&mut self, dt: f32, distance: f32) {
+        self.age += dt;
+        self.distance_travelled += distance;
+        let too_old = self.lifetime.is_some_and(|lifetime| self.age > lifetime);
+        let too_far = self
+            .range
+            .is_some_and(|range| self.distance_travelled > range);
+        if too_old || too_far {
+            self.destroyed = true;
+        }
+    }
+
+    fn warp_around(&mut self, world: &World) {
+        let unwrapped_point = self.point;
+        if self.point.x > world.width {
             self.point.x = 0.0;
         } else if self.point.x < 0.0 {
-            self.point.x = screen_width;
+            self.point.x = world.width;
         }
-        if self.point.y > screen_height {
+        if self.point.y > world.height {
             self.point.y = 0.0;
         } else if self.point.y < 0.0 {
-            self.point.y = screen_height;
+            self.point.y = world.height;
+        }
+        // move the previous point along so interpolation doesn't sweep across the screen
+        self.previous_point += self.point - unwrapped_point;
+    }
+
+    // Range `point` may move in along an axis `size` long, `extent` away from
+    // both ends, or just the middle for bodies too big to fit.
+    fn walls(&self, size: f32) -> (f32, f32) {
+        let margin = self.extent.min(size / 2.0);
+        (margin, size - margin)
+    }
+
+    fn bounce(&mut self, world: &World) {
+        let (min_x, max_x) = self.walls(world.width);
+        let (min_y, max_y) = self.walls(world.height);
+        if self.point.x < min_x || self.point.x > max_x {
+            self.point.x = self.point.x.clamp(min_x, max_x);
+            self.velocity.x = -self.velocity.x * self.wall_restitution;
+        }
+        if self.point.y < min_y || self.point.y > max_y {
+            self.point.y = self.point.y.clamp(min_y, max_y);
+            self.velocity.y = -self.velocity.y * self.wall_restitution;
+        }
+    }
+
+    fn clamp(&mut self, world: &World) {
+        let (min_x, max_x) = self.walls(world.width);
+        let (min_y, max_y) = self.walls(world.height);
+        if self.point.x < min_x || self.point.x > max_x {
+            self.point.x = self.point.x.clamp(min_x, max_x);
+            self.velocity.x = 0.0;
+        }
+        if self.point.y < min_y || self.point.y > max_y {
+            self.point.y = self.point.y.clamp(min_y, max_y);
+            self.velocity.y = 0.0;
         }
     }
 
-    fn destroy(&mut self) {
-        let screen_width = screen_width();
-        let screen_height = screen_height();
-        if self.point.x > screen_width
-            || self.point.x < 0.0
-            || self.point.y > screen_height
-            || self.point.y < 0.0
-        {
+    fn destroy(&mut self, world: &World) {
+        if !world.contains(self.point) {
             self.destroyed = true;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_stop_the_whole_shape() {
+        let world = World::new(800.0, 600.0);
+        let mut body = Body {
+            extent: 50.0,
+            ..Body::new(
+                vec2(760.0, 300.0),
+                0.0,
+                vec2(100.0, 0.0),
+                ScreenEdgeBehavior::Bounce,
+            )
+        };
+        body.update(0.1, &world);
+        assert_eq!(body.point.x, 750.0);
+        assert_eq!(body.velocity.x, -100.0);
+
+        body.screen_edge_behavior = ScreenEdgeBehavior::Clamp;
+        body.point = vec2(30.0, 580.0);
+        body.velocity = vec2(-10.0, 10.0);
+        body.update(0.1, &world);
+        assert_eq!(body.point, vec2(50.0, 550.0));
+        assert_eq!(body.velocity, Vec2::ZERO);
+    }
+
+    #[test]
+    fn walls_keep_the_speed_of_a_body_that_loses_it_in_collisions() {
+        let world = World::new(800.0, 600.0);
+        let mut body = Body {
+            restitution: 0.9,
+            ..Body::new(
+                vec2(795.0, 5.0),
+                0.0,
+                vec2(50.0, -50.0),
+                ScreenEdgeBehavior::Bounce,
+            )
+        };
+        body.update(0.2, &world);
+        assert_eq!(body.velocity, vec2(-50.0, 50.0));
+
+        body.wall_restitution = 0.5;
+        body.point = vec2(5.0, 300.0);
+        body.update(0.2, &world);
+        assert_eq!(body.velocity, vec2(25.0, 50.0));
+    }
+
+    #[test]
+    fn interpolation_transform_turns_cached_points_back() {
+        let mut body = Body::new(vec2(10.0, 0.0), 0.0, Vec2::ZERO, ScreenEdgeBehavior::Wrap);
+        body.save_previous();
+        body.point = vec2(20.0, 0.0);
+        body.rotation = 1.0;
+        // a point one unit ahead of the body, placed at the current pose
+        let cached = body.point + Vec2::from_angle(body.rotation);
+        let halfway = body.interpolation_transform(0.5).transform_point2(cached);
+        let expected = vec2(15.0, 0.0) + Vec2::from_angle(0.5);
+        assert!(halfway.distance(expected) < 1e-4);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::physics::world::World;
+use macroquad::prelude::*;
+
+// Axis aligned bounding box.
+// https://en.wikipedia.org/wiki/Minimum_bounding_box#Axis-aligned_minimum_bounding_box
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb {
+    pub fn from_points(points: &[Vec2]) -> Aabb {
+        let mut min = vec2(f32::INFINITY, f32::INFINITY);
+        let mut max = vec2(f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for point in points {
+            min = min.min(*point);
+            max = max.max(*point);
+        }
+        Aabb { min, max }
+    }
+
+    pub fn from_circle(circle: &BoundingCircle) -> Aabb {
+        let extent = vec2(circle.radius, circle.radius);
+        Aabb {
+            min: circle.center - extent,
+            max: circle.center + extent,
+        }
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BoundingCircle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl BoundingCircle {
+    pub fn overlaps(&self, other: &BoundingCircle) -> bool {
+        let radii = self.radius + other.radius;
+        self.center.distance_squared(other.center) <= radii * radii
+    }
+}
+
+// Uniform grid over the world that buckets objects by the cells their bounds
+// touch, so only objects sharing a cell need a narrow phase test.
+// Cells wrap around the world edges: bounds hanging off one side land in the
+// cells on the opposite side. For that the cells have to tile the world exactly,
+// so they're stretched a little where the world isn't a multiple of the cell size.
+// https://en.wikipedia.org/wiki/Spatial_hashing
+pub struct SpatialHash {
+    cell_size: Vec2,
+    columns: i32,
+    rows: i32,
+    cells: Vec<Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(world: &World, cell_size: f32) -> SpatialHash {
+        let columns = ((world.width / cell_size).ceil() as i32).max(1);
+        let rows = ((world.height / cell_size).ceil() as i32).max(1);
+        SpatialHash {
+            cell_size: vec2(world.width / columns as f32, world.height / rows as f32),
+            columns,
+            rows,
+            cells: vec![vec![]; (columns * rows) as usize],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| cell.clear());
+    }
+
+    fn cell_range(&self, bounds: &Aabb) -> (i32, i32, i32, i32) {
+        let min_column = (bounds.min.x / self.cell_size.x).floor() as i32;
+        let min_row = (bounds.min.y / self.cell_size.y).floor() as i32;
+        // an object can't cover a cell twice, even if it's bigger than the world
+        let max_column =
+            ((bounds.max.x / self.cell_size.x).floor() as i32).min(min_column + self.columns - 1);
+        let max_row =
+            ((bounds.max.y / self.cell_size.y).floor() as i32).min(min_row + self.rows - 1);
+        (min_column, min_row, max_column, max_row)
+    }
+
+    fn cell_index(&self, column: i32, row: i32) -> usize {
+        (row.rem_euclid(self.rows) * self.columns + column.rem_euclid(self.columns)) as usize
+    }
+
+    pub fn insert(&mut self, index: usize, bounds: &Aabb) {
+        let (min_column, min_row, max_column, max_row) = self.cell_range(bounds);
+        for row in min_row..=max_row {
+            for column in min_column..=max_column {
+                let cell = self.cell_index(column, row);
+                self.cells[cell].push(index);
+            }
+        }
+    }
+
+    // Fills `candidates` with the indices of every object sharing a cell with `bounds`.
+    pub fn query(&self, bounds: &Aabb, candidates: &mut Vec<usize>) {
+        candidates.clear();
+        let (min_column, min_row, max_column, max_row) = self.cell_range(bounds);
+        for row in min_row..=max_row {
+            for column in min_column..=max_column {
+                candidates.extend_from_slice(&self.cells[self.cell_index(column, row)]);
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(center: Vec2, half_size: f32) -> Aabb {
+        Aabb::from_circle(&BoundingCircle {
+            center,
+            radius: half_size,
+        })
+    }
+
+    #[test]
+    fn bounds_across_an_edge_are_found_from_the_opposite_edge() {
+        // 600 isn't a multiple of 80, the last row would reach past the world
+        let world = World::new(800.0, 600.0);
+        let mut grid = SpatialHash::new(&world, 80.0);
+        let mut candidates = vec![];
+
+        // hangs off the top, so part of it shows at the bottom
+        grid.insert(0, &square(vec2(400.0, 5.0), 50.0));
+        grid.query(&square(vec2(375.0, 551.0), 5.0), &mut candidates);
+        assert_eq!(candidates, vec![0]);
+
+        // and the same across the left and right edges
+        grid.insert(1, &square(vec2(795.0, 300.0), 20.0));
+        grid.query(&square(vec2(10.0, 300.0), 2.0), &mut candidates);
+        assert_eq!(candidates, vec![1]);
+    }
+
+    #[test]
+    fn distant_bounds_are_not_candidates() {
+        let world = World::new(800.0, 600.0);
+        let mut grid = SpatialHash::new(&world, 80.0);
+        let mut candidates = vec![];
+        grid.insert(0, &square(vec2(400.0, 300.0), 20.0));
+        grid.query(&square(vec2(100.0, 100.0), 20.0), &mut candidates);
+        assert!(candidates.is_empty());
+    }
+}
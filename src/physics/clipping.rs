@@ -0,0 +1,205 @@
+use crate::physics::polygon::Polygon;
+use macroquad::prelude::*;
+
+// Where the outline crosses the cutting line, and which way.
+struct Crossing {
+    // position along the line
+    along: f32,
+    // true where the outline comes back onto the kept side
+    entering: bool,
+    // the run of kept points this crossing starts or ends
+    run: usize,
+}
+
+// Parts of a closed polygon on the side of a line that `normal` points to.
+// A concave polygon the line cuts more than twice can fall apart into several
+// pieces, each comes back as its own polygon.
+// The outline is walked once to collect the runs of points on the kept side,
+// each starting and ending where it crosses the line. Along the line, crossings
+// pair up into the stretches that lie inside the polygon, and each stretch
+// joins the end of one run to the start of the next run of the same piece.
+pub fn clip_to_half_plane(polygon: &Polygon, line_point: Vec2, normal: Vec2) -> Vec<Polygon> {
+    let distance = |point: Vec2| (point - line_point).dot(normal);
+    let points = polygon.points();
+    let Some(first_dropped) = points.iter().position(|point| distance(*point) < 0.0) else {
+        // nothing to cut off
+        return vec![polygon.clone()];
+    };
+
+    // walk the outline starting from a dropped point, so every run is whole
+    let tangent = normal.perp();
+    let mut runs: Vec<Vec<Vec2>> = vec![];
+    let mut crossings: Vec<Crossing> = vec![];
+    let count = points.len();
+    for i in 0..count {
+        let start = points[(first_dropped + i) % count];
+        let end = points[(first_dropped + i + 1) % count];
+        let start_distance = distance(start);
+        let end_distance = distance(end);
+        if start_distance >= 0.0 {
+            runs.last_mut()
+                .expect("a run starts before its points")
+                .push(start);
+        }
+        if (start_distance >= 0.0) != (end_distance >= 0.0) {
+            let t = start_distance / (start_distance - end_distance);
+            let point = start.lerp(end, t);
+            let entering = end_distance >= 0.0;
+            if entering {
+                runs.push(vec![point]);
+            } else {
+                runs.last_mut()
+                    .expect("a run is entered before it's left")
+                    .push(point);
+            }
+            crossings.push(Crossing {
+                along: (point - line_point).dot(tangent),
+                entering,
+                run: runs.len() - 1,
+            });
+        }
+    }
+
+    // each stretch of the line inside the polygon leads from a run's end to
+    // the start of the run that continues the piece
+    crossings.sort_by(|a, b| a.along.total_cmp(&b.along));
+    let mut next_run: Vec<Option<usize>> = vec![None; runs.len()];
+    for stretch in crossings.chunks(2) {
+        match stretch {
+            [a, b] if a.entering != b.entering => {
+                let (leaving, entering) = if a.entering { (b, a) } else { (a, b) };
+                next_run[leaving.run] = Some(entering.run);
+            }
+            // only happens if the outline crosses itself
+            _ => return vec![],
+        }
+    }
+
+    let mut pieces: Vec<Polygon> = vec![];
+    let mut used = vec![false; runs.len()];
+    for first in 0..runs.len() {
+        let mut outline: Vec<Vec2> = vec![];
+        let mut run = first;
+        while !used[run] {
+            used[run] = true;
+            outline.extend_from_slice(&runs[run]);
+            match next_run[run] {
+                Some(next) => run = next,
+                None => break,
+            }
+        }
+        if let Ok(piece) = Polygon::closed(outline) {
+            if piece.area() > 0.0 {
+                pieces.push(piece);
+            }
+        }
+    }
+    pieces
+}
+
+// Cuts a closed polygon along the line through `line_point` in `direction`,
+// returning the pieces on either side.
+pub fn split_polygon(
+    polygon: &Polygon,
+    line_point: Vec2,
+    direction: Vec2,
+) -> (Vec<Polygon>, Vec<Polygon>) {
+    let normal = direction.perp();
+    (
+        clip_to_half_plane(polygon, line_point, normal),
+        clip_to_half_plane(polygon, line_point, -normal),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{actual} is not {expected}"
+        );
+    }
+
+    #[test]
+    fn convex_cut_gives_one_piece_per_side() {
+        let square = Polygon::closed(vec![
+            vec2(0.0, 0.0),
+            vec2(10.0, 0.0),
+            vec2(10.0, 10.0),
+            vec2(0.0, 10.0),
+        ])
+        .unwrap();
+        let (left, right) = split_polygon(&square, vec2(4.0, 5.0), vec2(0.0, 1.0));
+        assert_eq!(left.len(), 1);
+        assert_eq!(right.len(), 1);
+        assert_near(left[0].area() + right[0].area(), 100.0);
+        // the line is x = 4, so one side is 4 wide and the other 6
+        let mut areas = [left[0].area(), right[0].area()];
+        areas.sort_by(f32::total_cmp);
+        assert_near(areas[0], 40.0);
+        assert_near(areas[1], 60.0);
+    }
+
+    #[test]
+    fn line_missing_the_polygon_keeps_it_whole() {
+        let triangle =
+            Polygon::closed(vec![vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(0.0, 10.0)]).unwrap();
+        let (above, below) = split_polygon(&triangle, vec2(0.0, 20.0), vec2(1.0, 0.0));
+        assert_eq!(above.len() + below.len(), 1);
+        assert_near(
+            above.iter().chain(below.iter()).next().unwrap().area(),
+            50.0,
+        );
+    }
+
+    #[test]
+    fn concave_cut_falls_apart_into_connected_pieces() {
+        // a U opening upwards, cut straight across both arms
+        let u = Polygon::closed(vec![
+            vec2(0.0, 0.0),
+            vec2(30.0, 0.0),
+            vec2(30.0, 30.0),
+            vec2(20.0, 30.0),
+            vec2(20.0, 10.0),
+            vec2(10.0, 10.0),
+            vec2(10.0, 30.0),
+            vec2(0.0, 30.0),
+        ])
+        .unwrap();
+        let arms = clip_to_half_plane(&u, vec2(0.0, 20.0), vec2(0.0, 1.0));
+        let base = clip_to_half_plane(&u, vec2(0.0, 20.0), vec2(0.0, -1.0));
+
+        assert_eq!(arms.len(), 2);
+        for arm in arms.iter() {
+            assert_near(arm.area(), 100.0);
+            // a 10 x 10 square, nothing joining it to the other arm
+            let aabb = arm.aabb();
+            assert_near(aabb.max.x - aabb.min.x, 10.0);
+            assert_near(aabb.max.y - aabb.min.y, 10.0);
+        }
+        assert_eq!(base.len(), 1);
+        assert_near(base[0].area(), 30.0 * 10.0 + 2.0 * 10.0 * 10.0);
+    }
+
+    #[test]
+    fn concave_cut_through_one_arm_keeps_each_side_whole() {
+        // the same U cut lengthwise through its left arm and the base
+        let u = Polygon::closed(vec![
+            vec2(0.0, 0.0),
+            vec2(30.0, 0.0),
+            vec2(30.0, 30.0),
+            vec2(20.0, 30.0),
+            vec2(20.0, 10.0),
+            vec2(10.0, 10.0),
+            vec2(10.0, 30.0),
+            vec2(0.0, 30.0),
+        ])
+        .unwrap();
+        let (left, right) = split_polygon(&u, vec2(5.0, 0.0), vec2(0.0, 1.0));
+        assert_eq!(left.len(), 1);
+        assert_eq!(right.len(), 1);
+        assert_near(left[0].area() + right[0].area(), u.area());
+    }
+}
@@ -1,40 +1,433 @@
+use crate::physics::polygon::Polygon;
 use macroquad::prelude::*;
 
-pub fn is_colliding(polygon_a: &Vec<Vec2>, polygon_b: &Vec<Vec2>) -> bool {
+pub fn is_colliding(polygon_a: &Polygon, polygon_b: &Polygon) -> bool {
     // 2D physics collision detection
     // assume points create a concave polygon
-    for point_a in polygon_a.iter() {
+    // vertices of either polygon inside the other catch containment,
+    // crossing edges catch overlaps where no vertex is inside (e.g. line segments)
+    for point_a in polygon_a.points() {
         if point_in_polygon(point_a, polygon_b) {
             return true;
         }
     }
-    return false;
+    for point_b in polygon_b.points() {
+        if point_in_polygon(point_b, polygon_a) {
+            return true;
+        }
+    }
+    edges_intersect(polygon_a, polygon_b)
 }
 
-pub fn point_in_polygon(point: &Vec2, polygon: &Vec<Vec2>) -> bool {
+pub fn point_in_polygon(point: &Vec2, polygon: &Polygon) -> bool {
     // https://en.wikipedia.org/wiki/Point_in_polygon
+    if !polygon.is_closed() {
+        // a polyline has no inside
+        return false;
+    }
     let x = point.x;
     let y = point.y;
     let mut inside = false;
 
-    let mut point_1 = polygon.first().unwrap();
-    for point_2 in polygon.iter().skip(1) {
-        if y > point_1.y.min(point_2.y) {
-            if y <= point_1.y.max(point_2.y) {
-                if x <= point_1.x.max(point_2.x) {
-                    let x_intercept = if point_1.y != point_2.y {
-                        (y - point_1.y) * (point_2.x - point_1.x) / (point_2.y - point_1.y)
-                            + point_1.x
-                    } else {
-                        point_1.x
-                    };
-                    if point_1.x == point_2.x || x <= x_intercept {
-                        inside = !inside;
-                    }
-                }
+    for (point_1, point_2) in polygon.edges() {
+        if y > point_1.y.min(point_2.y)
+            && y <= point_1.y.max(point_2.y)
+            && x <= point_1.x.max(point_2.x)
+        {
+            let x_intercept = if point_1.y != point_2.y {
+                (y - point_1.y) * (point_2.x - point_1.x) / (point_2.y - point_1.y) + point_1.x
+            } else {
+                point_1.x
+            };
+            if point_1.x == point_2.x || x <= x_intercept {
+                inside = !inside;
             }
         }
-        point_1 = point_2;
     }
     inside
 }
+
+// Sign of the turn a -> b -> c: > 0 counter clockwise, < 0 clockwise, 0 collinear.
+fn orientation(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    (b - a).perp_dot(c - a)
+}
+
+fn on_segment(start: Vec2, end: Vec2, point: Vec2) -> bool {
+    point.x >= start.x.min(end.x)
+        && point.x <= start.x.max(end.x)
+        && point.y >= start.y.min(end.y)
+        && point.y <= start.y.max(end.y)
+}
+
+// https://en.wikipedia.org/wiki/Intersection_(geometry)#Two_line_segments
+pub fn segments_intersect(a_start: Vec2, a_end: Vec2, b_start: Vec2, b_end: Vec2) -> bool {
+    let d1 = orientation(b_start, b_end, a_start);
+    let d2 = orientation(b_start, b_end, a_end);
+    let d3 = orientation(a_start, a_end, b_start);
+    let d4 = orientation(a_start, a_end, b_end);
+
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+
+    // collinear touching cases
+    (d1 == 0.0 && on_segment(b_start, b_end, a_start))
+        || (d2 == 0.0 && on_segment(b_start, b_end, a_end))
+        || (d3 == 0.0 && on_segment(a_start, a_end, b_start))
+        || (d4 == 0.0 && on_segment(a_start, a_end, b_end))
+}
+
+pub fn edges_intersect(polygon_a: &Polygon, polygon_b: &Polygon) -> bool {
+    for (a_start, a_end) in polygon_a.edges() {
+        for (b_start, b_end) in polygon_b.edges() {
+            if segments_intersect(a_start, a_end, b_start, b_end) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+pub fn segment_intersects_polygon(start: Vec2, end: Vec2, polygon: &Polygon) -> bool {
+    if point_in_polygon(&start, polygon) || point_in_polygon(&end, polygon) {
+        return true;
+    }
+    polygon
+        .edges()
+        .any(|(edge_start, edge_end)| segments_intersect(start, end, edge_start, edge_end))
+}
+
+// Distance along a ray from `origin` in the unit `direction` to where it first
+// enters the polygon, up to `max_distance`. Zero if it starts inside.
+pub fn ray_cast(
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+    polygon: &Polygon,
+) -> Option<f32> {
+    if point_in_polygon(&origin, polygon) {
+        return Some(0.0);
+    }
+    let end = origin + direction * max_distance;
+    polygon
+        .edges()
+        .filter_map(|(edge_start, edge_end)| {
+            segment_intersection(origin, end, edge_start, edge_end)
+        })
+        .map(|point| point.distance(origin))
+        .min_by(f32::total_cmp)
+}
+
+// Tests the area a segment swept through between two steps, so fast moving
+// segments can't tunnel through thin or small polygons.
+pub fn swept_segment_intersects_polygon(
+    previous: (Vec2, Vec2),
+    current: (Vec2, Vec2),
+    polygon: &Polygon,
+) -> bool {
+    match Polygon::closed(vec![previous.0, previous.1, current.1, current.0]) {
+        Ok(swept_area) => is_colliding(&swept_area, polygon),
+        // a segment that went somewhere not finite can't hit anything
+        Err(_) => false,
+    }
+}
+
+// Where and how deeply two polygons overlap.
+#[derive(Debug, Clone)]
+pub struct ContactManifold {
+    // crossing points of the outlines and vertices inside the other polygon
+    pub points: Vec<Vec2>,
+    // unit vector pointing from polygon a towards polygon b
+    pub normal: Vec2,
+    // how far a and b have to move apart along the normal to separate
+    pub penetration_depth: f32,
+    // velocity of b as seen from a
+    pub relative_velocity: Vec2,
+}
+
+impl ContactManifold {
+    // Speed at which the polygons approach each other along the normal, negative when separating.
+    pub fn closing_speed(&self) -> f32 {
+        -self.relative_velocity.dot(self.normal)
+    }
+}
+
+pub fn segment_intersection(
+    a_start: Vec2,
+    a_end: Vec2,
+    b_start: Vec2,
+    b_end: Vec2,
+) -> Option<Vec2> {
+    // https://en.wikipedia.org/wiki/Line%E2%80%93line_intersection#Given_two_points_on_each_line_segment
+    let a = a_end - a_start;
+    let b = b_end - b_start;
+    let denominator = a.perp_dot(b);
+    if denominator == 0.0 {
+        // parallel, collinear overlaps have no single crossing point
+        return None;
+    }
+    let t = (b_start - a_start).perp_dot(b) / denominator;
+    let u = (b_start - a_start).perp_dot(a) / denominator;
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        return Some(a_start + a * t);
+    }
+    None
+}
+
+pub fn closest_point_on_segment(point: Vec2, start: Vec2, end: Vec2) -> Vec2 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return start;
+    }
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    start + segment * t
+}
+
+fn push_unique(points: &mut Vec<Vec2>, point: Vec2) {
+    if !points.iter().any(|p| p.distance_squared(point) < 1e-6) {
+        points.push(point);
+    }
+}
+
+// https://en.wikipedia.org/wiki/Convex_hull_algorithms#Monotone_chain
+fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    // lower half left to right, then the upper half back, dropping every
+    // point the outline doesn't turn counter clockwise at
+    let mut hull: Vec<Vec2> = Vec::with_capacity(sorted.len() + 1);
+    let mut half_start = 0;
+    let lower_then_upper = sorted.iter().chain(sorted.iter().rev().skip(1));
+    for (i, point) in lower_then_upper.enumerate() {
+        if i == sorted.len() {
+            half_start = hull.len() - 1;
+        }
+        while hull.len() >= half_start + 2
+            && orientation(hull[hull.len() - 2], hull[hull.len() - 1], *point) <= 0.0
+        {
+            hull.pop();
+        }
+        hull.push(*point);
+    }
+    // back at the first point
+    hull.pop();
+    hull
+}
+
+// Smallest overlap of the two point sets projected onto any edge normal of
+// either, and the axis it's on. Zero or less means the hulls are apart.
+// https://en.wikipedia.org/wiki/Hyperplane_separation_theorem
+fn minimum_translation(hull_a: &[Vec2], hull_b: &[Vec2]) -> (Vec2, f32) {
+    let project = |hull: &[Vec2], axis: Vec2| {
+        hull.iter()
+            .map(|point| point.dot(axis))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+                (min.min(d), max.max(d))
+            })
+    };
+    let edge_normals = |hull: &[Vec2]| {
+        let count = hull.len();
+        (0..count)
+            .filter_map(move |i| (hull[(i + 1) % count] - hull[i]).perp().try_normalize())
+            .collect::<Vec<Vec2>>()
+    };
+    let mut best = (Vec2::ZERO, f32::INFINITY);
+    for axis in edge_normals(hull_a).into_iter().chain(edge_normals(hull_b)) {
+        let (min_a, max_a) = project(hull_a, axis);
+        let (min_b, max_b) = project(hull_b, axis);
+        let overlap = max_a.min(max_b) - min_a.max(min_b);
+        if overlap < best.1 {
+            best = (axis, overlap);
+        }
+    }
+    best
+}
+
+// Contact information for two overlapping polygons, or None if they don't touch.
+// Contact points come from the outlines as they are, concave or not. The normal
+// and depth are the minimum translation that separates their convex hulls.
+pub fn contact_manifold(
+    polygon_a: &Polygon,
+    velocity_a: Vec2,
+    polygon_b: &Polygon,
+    velocity_b: Vec2,
+) -> Option<ContactManifold> {
+    let mut points: Vec<Vec2> = vec![];
+    for point in polygon_a.points() {
+        if point_in_polygon(point, polygon_b) {
+            push_unique(&mut points, *point);
+        }
+    }
+    for point in polygon_b.points() {
+        if point_in_polygon(point, polygon_a) {
+            push_unique(&mut points, *point);
+        }
+    }
+    for (a_start, a_end) in polygon_a.edges() {
+        for (b_start, b_end) in polygon_b.edges() {
+            if let Some(point) = segment_intersection(a_start, a_end, b_start, b_end) {
+                push_unique(&mut points, point);
+            }
+        }
+    }
+    if points.is_empty() {
+        return None;
+    }
+
+    let (axis, overlap) = minimum_translation(
+        &convex_hull(polygon_a.points()),
+        &convex_hull(polygon_b.points()),
+    );
+    let between = polygon_b.centroid() - polygon_a.centroid();
+    let (normal, penetration_depth) = if axis == Vec2::ZERO {
+        // no edge to take an axis from, e.g. two points
+        (between.try_normalize().unwrap_or(Vec2::X), 0.0)
+    } else if axis.dot(between) < 0.0 {
+        (-axis, overlap.max(0.0))
+    } else {
+        (axis, overlap.max(0.0))
+    };
+    Some(ContactManifold {
+        points,
+        normal,
+        penetration_depth,
+        relative_velocity: velocity_b - velocity_a,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rectangle(min: Vec2, max: Vec2) -> Polygon {
+        Polygon::closed(vec![min, vec2(max.x, min.y), max, vec2(min.x, max.y)]).unwrap()
+    }
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.distance(expected) < 1e-4,
+            "{actual} is not {expected}"
+        );
+    }
+
+    #[test]
+    fn manifold_separates_along_the_shallowest_axis() {
+        let a = rectangle(vec2(-10.0, -10.0), vec2(10.0, 10.0));
+        let b = rectangle(vec2(5.0, -7.0), vec2(25.0, 13.0));
+        let manifold = contact_manifold(&a, Vec2::ZERO, &b, vec2(-10.0, 0.0)).unwrap();
+        assert_near(manifold.normal, vec2(1.0, 0.0));
+        assert!((manifold.penetration_depth - 5.0).abs() < 1e-4);
+        // a head on approach closes along the normal
+        assert!((manifold.closing_speed() - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn manifold_normal_points_from_a_to_b() {
+        let a = rectangle(vec2(5.0, -7.0), vec2(25.0, 13.0));
+        let b = rectangle(vec2(-10.0, -10.0), vec2(10.0, 10.0));
+        let manifold = contact_manifold(&a, Vec2::ZERO, &b, Vec2::ZERO).unwrap();
+        assert_near(manifold.normal, vec2(-1.0, 0.0));
+        assert!((manifold.penetration_depth - 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn manifold_with_collinear_edges_has_depth() {
+        let a = rectangle(vec2(0.0, 0.0), vec2(10.0, 10.0));
+        let b = rectangle(vec2(5.0, 0.0), vec2(15.0, 10.0));
+        let manifold = contact_manifold(&a, Vec2::ZERO, &b, Vec2::ZERO).unwrap();
+        assert_near(manifold.normal, vec2(1.0, 0.0));
+        assert!((manifold.penetration_depth - 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn manifold_of_concave_outlines_uses_their_hulls() {
+        // an L with a square over the end of its foot
+        let l = Polygon::closed(vec![
+            vec2(0.0, 0.0),
+            vec2(30.0, 0.0),
+            vec2(30.0, 10.0),
+            vec2(10.0, 10.0),
+            vec2(10.0, 30.0),
+            vec2(0.0, 30.0),
+        ])
+        .unwrap();
+        let square = rectangle(vec2(26.0, -5.0), vec2(36.0, 5.0));
+        let manifold = contact_manifold(&l, Vec2::ZERO, &square, Vec2::ZERO).unwrap();
+        assert_near(manifold.normal, vec2(1.0, 0.0));
+        assert!((manifold.penetration_depth - 4.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn convex_hull_drops_inner_corners() {
+        let points = [
+            vec2(0.0, 0.0),
+            vec2(30.0, 0.0),
+            vec2(30.0, 10.0),
+            vec2(10.0, 10.0),
+            vec2(10.0, 30.0),
+            vec2(0.0, 30.0),
+        ];
+        let hull = convex_hull(&points);
+        assert_eq!(
+            hull,
+            vec![
+                vec2(0.0, 0.0),
+                vec2(30.0, 0.0),
+                vec2(30.0, 10.0),
+                vec2(10.0, 30.0),
+                vec2(0.0, 30.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_manifold_when_apart() {
+        let a = rectangle(vec2(0.0, 0.0), vec2(10.0, 10.0));
+        let b = rectangle(vec2(20.0, 0.0), vec2(30.0, 10.0));
+        assert!(contact_manifold(&a, Vec2::ZERO, &b, Vec2::ZERO).is_none());
+    }
+
+    #[test]
+    fn segment_through_a_polygon_hits_without_a_vertex_inside() {
+        let square = rectangle(vec2(0.0, 0.0), vec2(10.0, 10.0));
+        // both ends outside and no corner of the square on the segment
+        assert!(segment_intersects_polygon(
+            vec2(-5.0, 5.0),
+            vec2(15.0, 5.0),
+            &square
+        ));
+        // passes just above the top edge
+        assert!(!segment_intersects_polygon(
+            vec2(-5.0, 10.1),
+            vec2(15.0, 10.1),
+            &square
+        ));
+    }
+
+    #[test]
+    fn fast_streak_that_jumped_over_a_polygon_still_hits() {
+        // a small rock, and a 5 long streak that moved 50 in one step,
+        // from entirely in front of it to entirely past it
+        let rock = rectangle(vec2(20.0, -4.0), vec2(28.0, 4.0));
+        let previous = (vec2(0.0, 0.0), vec2(5.0, 0.0));
+        let current = (vec2(50.0, 0.0), vec2(55.0, 0.0));
+        assert!(!segment_intersects_polygon(previous.0, previous.1, &rock));
+        assert!(!segment_intersects_polygon(current.0, current.1, &rock));
+        assert!(swept_segment_intersects_polygon(previous, current, &rock));
+
+        // the same step passing beside the rock
+        let beside = |(start, end): (Vec2, Vec2)| (start + vec2(0.0, 5.0), end + vec2(0.0, 5.0));
+        assert!(!swept_segment_intersects_polygon(
+            beside(previous),
+            beside(current),
+            &rock
+        ));
+    }
+}
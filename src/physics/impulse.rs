@@ -0,0 +1,88 @@
+use crate::physics::body::Body;
+use crate::physics::collision::ContactManifold;
+
+// Share of the penetration removed per step, the rest is left for later steps
+// so stacked contacts don't jitter.
+const POSITION_CORRECTION: f32 = 0.8;
+// Penetration allowed before positions are corrected.
+const PENETRATION_SLOP: f32 = 0.1;
+
+// Bounces two colliding bodies off each other along the manifold normal.
+// The impulse is equal and opposite, so total momentum is conserved.
+// https://en.wikipedia.org/wiki/Collision_response#Impulse-based_contact_model
+pub fn resolve_collision(a: &mut Body, b: &mut Body, manifold: &ContactManifold) {
+    let inverse_mass_a = a.inverse_mass();
+    let inverse_mass_b = b.inverse_mass();
+    let inverse_mass_sum = inverse_mass_a + inverse_mass_b;
+    if inverse_mass_sum == 0.0 {
+        return;
+    }
+    let normal = manifold.normal;
+
+    // push the bodies apart, heavier bodies move less
+    let correction = normal
+        * ((manifold.penetration_depth - PENETRATION_SLOP).max(0.0) * POSITION_CORRECTION
+            / inverse_mass_sum);
+    a.point -= correction * inverse_mass_a;
+    b.point += correction * inverse_mass_b;
+
+    // use the current velocities, the manifold may be from before an earlier resolution
+    let velocity_along_normal = (b.velocity - a.velocity).dot(normal);
+    if velocity_along_normal > 0.0 {
+        // already separating
+        return;
+    }
+    let restitution = a.restitution.min(b.restitution);
+    let impulse = -(1.0 + restitution) * velocity_along_normal / inverse_mass_sum;
+    a.velocity -= normal * impulse * inverse_mass_a;
+    b.velocity += normal * impulse * inverse_mass_b;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::body::ScreenEdgeBehavior;
+    use crate::physics::collision::contact_manifold;
+    use crate::physics::polygon::Polygon;
+    use macroquad::prelude::*;
+
+    fn square(center: Vec2, half: f32) -> Polygon {
+        Polygon::closed(vec![
+            center + vec2(-half, -half),
+            center + vec2(half, -half),
+            center + vec2(half, half),
+            center + vec2(-half, half),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn equal_masses_keep_their_total_momentum() {
+        let mut a = Body::new(Vec2::ZERO, 0.0, vec2(10.0, 0.0), ScreenEdgeBehavior::Wrap);
+        let mut b = Body::new(
+            vec2(15.0, 3.0),
+            0.0,
+            vec2(-10.0, 2.0),
+            ScreenEdgeBehavior::Wrap,
+        );
+        let manifold = contact_manifold(
+            &square(a.point, 10.0),
+            a.velocity,
+            &square(b.point, 10.0),
+            b.velocity,
+        )
+        .unwrap();
+        let momentum_before = a.momentum() + b.momentum();
+        let b_before = b.point;
+
+        resolve_collision(&mut a, &mut b, &manifold);
+
+        let momentum_after = a.momentum() + b.momentum();
+        assert!(momentum_after.distance(momentum_before) < 1e-4);
+        // head on along x, perfectly elastic, so the x velocities swap
+        assert!(a.velocity.distance(vec2(-10.0, 0.0)) < 1e-4);
+        assert!(b.velocity.distance(vec2(10.0, 2.0)) < 1e-4);
+        // and b is pushed out of a, not further in
+        assert!(b.point.x > b_before.x);
+    }
+}
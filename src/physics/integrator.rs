@@ -0,0 +1,164 @@
+use macroquad::prelude::*;
+
+// Numerical methods for advancing a body's position and velocity.
+// Linear drag is passed separately from the other accelerations so every
+// method can decay velocity as e^(-drag * t) regardless of step size.
+// https://gafferongames.com/post/integration_basics/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Integrator {
+    // cheap, stable for plain thrust and drag, what the game used originally
+    SemiImplicitEuler,
+    // second order, conserves energy well in orbits
+    VelocityVerlet,
+    // fourth order Runge-Kutta, the most accurate per step
+    RungeKutta4,
+}
+
+impl Integrator {
+    // Advances `point` and `velocity` by `dt` seconds. `acceleration` gives the
+    // acceleration at a position (thrust, gravity wells...), excluding drag.
+    pub fn integrate(
+        &self,
+        point: &mut Vec2,
+        velocity: &mut Vec2,
+        linear_drag: f32,
+        dt: f32,
+        acceleration: &dyn Fn(Vec2) -> Vec2,
+    ) {
+        match self {
+            Integrator::SemiImplicitEuler => {
+                *velocity += acceleration(*point) * dt;
+                // exact decay over dt rather than a per step factor
+                *velocity *= (-linear_drag * dt).exp();
+                *point += *velocity * dt;
+            }
+            Integrator::VelocityVerlet => {
+                // https://en.wikipedia.org/wiki/Verlet_integration#Velocity_Verlet
+                let start_acceleration = acceleration(*point) - *velocity * linear_drag;
+                *point += *velocity * dt + start_acceleration * (0.5 * dt * dt);
+                // drag depends on velocity, estimate the end velocity for it
+                let predicted_velocity = *velocity + start_acceleration * dt;
+                let end_acceleration = acceleration(*point) - predicted_velocity * linear_drag;
+                *velocity += (start_acceleration + end_acceleration) * (0.5 * dt);
+            }
+            Integrator::RungeKutta4 => {
+                // https://en.wikipedia.org/wiki/Runge%E2%80%93Kutta_methods
+                let derivative =
+                    |p: Vec2, v: Vec2| -> (Vec2, Vec2) { (v, acceleration(p) - v * linear_drag) };
+                let (k1_p, k1_v) = derivative(*point, *velocity);
+                let (k2_p, k2_v) =
+                    derivative(*point + k1_p * (0.5 * dt), *velocity + k1_v * (0.5 * dt));
+                let (k3_p, k3_v) =
+                    derivative(*point + k2_p * (0.5 * dt), *velocity + k2_v * (0.5 * dt));
+                let (k4_p, k4_v) = derivative(*point + k3_p * dt, *velocity + k3_v * dt);
+                *point += (k1_p + k2_p * 2.0 + k3_p * 2.0 + k4_p) * (dt / 6.0);
+                *velocity += (k1_v + k2_v * 2.0 + k3_v * 2.0 + k4_v) * (dt / 6.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::body::{Body, ScreenEdgeBehavior};
+    use crate::physics::world::World;
+
+    const ALL: [Integrator; 3] = [
+        Integrator::SemiImplicitEuler,
+        Integrator::VelocityVerlet,
+        Integrator::RungeKutta4,
+    ];
+
+    // Steps `integrator` for `seconds`, returns the position and velocity.
+    fn run(
+        integrator: Integrator,
+        point: Vec2,
+        velocity: Vec2,
+        linear_drag: f32,
+        seconds: f32,
+        dt: f32,
+        acceleration: &dyn Fn(Vec2) -> Vec2,
+    ) -> (Vec2, Vec2) {
+        let (mut point, mut velocity) = (point, velocity);
+        for _ in 0..(seconds / dt).round() as usize {
+            integrator.integrate(&mut point, &mut velocity, linear_drag, dt, acceleration);
+        }
+        (point, velocity)
+    }
+
+    #[test]
+    fn drag_decays_velocity_exponentially() {
+        // v = v0 e^(-kt), x = v0 / k (1 - e^(-kt))
+        let (v0, k, t): (f32, f32, f32) = (100.0, 0.6, 2.0);
+        let expected_velocity = v0 * (-k * t).exp();
+        let expected_point = v0 / k * (1.0 - (-k * t).exp());
+        for integrator in ALL {
+            let (point, velocity) = run(
+                integrator,
+                Vec2::ZERO,
+                vec2(v0, 0.0),
+                k,
+                t,
+                1.0 / 120.0,
+                &|_| Vec2::ZERO,
+            );
+            assert!(
+                (velocity.x - expected_velocity).abs() < 0.05,
+                "{integrator:?} velocity {} is not {expected_velocity}",
+                velocity.x
+            );
+            assert!(
+                (point.x - expected_point).abs() < 0.5,
+                "{integrator:?} point {} is not {expected_point}",
+                point.x
+            );
+        }
+    }
+
+    #[test]
+    fn harmonic_oscillator_follows_cos_t() {
+        // a = -x, starting at rest at x = 1, so x = cos t and v = -sin t
+        let spring = |point: Vec2| -point;
+        let t = 2.0 * std::f32::consts::PI;
+        for (integrator, tolerance) in [
+            (Integrator::SemiImplicitEuler, 2e-2),
+            (Integrator::VelocityVerlet, 1e-3),
+            (Integrator::RungeKutta4, 1e-4),
+        ] {
+            let (point, velocity) =
+                run(integrator, Vec2::X, Vec2::ZERO, 0.0, t, t / 600.0, &spring);
+            assert!(
+                (point.x - t.cos()).abs() < tolerance,
+                "{integrator:?} x {} is not {}",
+                point.x,
+                t.cos()
+            );
+            assert!((velocity.x + t.sin()).abs() < tolerance);
+        }
+    }
+
+    #[test]
+    fn body_substeps_in_a_field() {
+        // the same oscillator through `Body::update_in_field`, one update per
+        // quarter period split into substeps
+        let world = World::new(800.0, 600.0);
+        let center = world.center();
+        let mut body = Body {
+            integrator: Integrator::RungeKutta4,
+            substeps: 50,
+            ..Body::new(center + Vec2::X, 0.0, Vec2::ZERO, ScreenEdgeBehavior::Wrap)
+        };
+        let quarter = std::f32::consts::FRAC_PI_2;
+        for i in 1..=4 {
+            body.update_in_field(quarter, &world, &|point| center - point);
+            let t = quarter * i as f32;
+            let expected = center + vec2(t.cos(), 0.0);
+            assert!(
+                body.point.distance(expected) < 1e-3,
+                "after {t} the body is at {} not {expected}",
+                body.point
+            );
+        }
+    }
+}
@@ -1,2 +1,8 @@
 pub mod body;
+pub mod broadphase;
+pub mod clipping;
 pub mod collision;
+pub mod impulse;
+pub mod integrator;
+pub mod polygon;
+pub mod world;
@@ -0,0 +1,349 @@
+use crate::physics::broadphase::Aabb;
+use crate::physics::collision::segments_intersect;
+use macroquad::prelude::*;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolygonError {
+    TooFewPoints { required: usize, found: usize },
+    NonFinitePoint { index: usize },
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolygonError::TooFewPoints { required, found } => {
+                write!(
+                    f,
+                    "polygon needs at least {} points, got {}",
+                    required, found
+                )
+            }
+            PolygonError::NonFinitePoint { index } => {
+                write!(f, "polygon point {} is not finite", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+// Direction the vertices go around in, with y pointing up. On screen, where y
+// points down, it looks mirrored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+    // no area, e.g. all points on a line
+    Degenerate,
+}
+
+// Outline checked once when it is built, so drawing and collision code never
+// has to deal with empty or broken point lists.
+// A closed polygon joins its last point back to the first, an open one is a
+// polyline (a bullet streak, flames).
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    points: Vec<Vec2>,
+    closed: bool,
+}
+
+impl Polygon {
+    // At least 3 points. Repeating the first point at the end is allowed but not needed.
+    pub fn closed(mut points: Vec<Vec2>) -> Result<Polygon, PolygonError> {
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        Polygon::validate(&points, 3)?;
+        Ok(Polygon {
+            points,
+            closed: true,
+        })
+    }
+
+    // At least 2 points, the ends aren't joined.
+    pub fn open(points: Vec<Vec2>) -> Result<Polygon, PolygonError> {
+        Polygon::validate(&points, 2)?;
+        Ok(Polygon {
+            points,
+            closed: false,
+        })
+    }
+
+    fn validate(points: &[Vec2], required: usize) -> Result<(), PolygonError> {
+        if points.len() < required {
+            return Err(PolygonError::TooFewPoints {
+                required,
+                found: points.len(),
+            });
+        }
+        if let Some(index) = points.iter().position(|point| !point.is_finite()) {
+            return Err(PolygonError::NonFinitePoint { index });
+        }
+        Ok(())
+    }
+
+    pub fn points(&self) -> &[Vec2] {
+        &self.points
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    // New polygon with `f` applied to every point, keeping open/closed.
+    pub fn map(&self, f: impl Fn(Vec2) -> Vec2) -> Polygon {
+        Polygon {
+            points: self.points.iter().map(|point| f(*point)).collect(),
+            closed: self.closed,
+        }
+    }
+
+    // Rotates around the origin then translates every point into `target`,
+    // reusing its buffer. sin and cos are computed once for all points.
+    pub fn transform_into(&self, target: &mut Polygon, translation: Vec2, rotation: f32) {
+        let (sin, cos) = rotation.sin_cos();
+        target.points.clear();
+        target.points.extend(self.points.iter().map(|point| {
+            vec2(
+                translation.x + point.x * cos - point.y * sin,
+                translation.y + point.x * sin + point.y * cos,
+            )
+        }));
+        target.closed = self.closed;
+    }
+
+    // Like `transform_into`, for any affine transform such as a scaled or nested one.
+    pub fn transform_matrix_into(&self, target: &mut Polygon, matrix: &Mat3) {
+        target.points.clear();
+        target.points.extend(
+            self.points
+                .iter()
+                .map(|point| matrix.transform_point2(*point)),
+        );
+        target.closed = self.closed;
+    }
+
+    // Segments of the outline, including the closing one for closed polygons.
+    pub fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        let closing_edge = if self.closed {
+            Some((self.points[self.points.len() - 1], self.points[0]))
+        } else {
+            None
+        };
+        self.points
+            .windows(2)
+            .map(|edge| (edge[0], edge[1]))
+            .chain(closing_edge)
+    }
+
+    // https://en.wikipedia.org/wiki/Shoelace_formula
+    pub fn signed_area(&self) -> f32 {
+        if !self.closed {
+            return 0.0;
+        }
+        self.edges().map(|(a, b)| a.perp_dot(b)).sum::<f32>() / 2.0
+    }
+
+    pub fn area(&self) -> f32 {
+        self.signed_area().abs()
+    }
+
+    // Center of mass of the enclosed area, or the average point for open or flat polygons.
+    // https://en.wikipedia.org/wiki/Centroid#Of_a_polygon
+    pub fn centroid(&self) -> Vec2 {
+        let signed_area = self.signed_area();
+        if signed_area.abs() < f32::EPSILON {
+            return self.points.iter().fold(Vec2::ZERO, |sum, p| sum + *p)
+                / self.points.len() as f32;
+        }
+        let weighted = self
+            .edges()
+            .fold(Vec2::ZERO, |sum, (a, b)| sum + (a + b) * a.perp_dot(b));
+        weighted / (6.0 * signed_area)
+    }
+
+    // Radius of the circle around the origin that contains every point.
+    pub fn bounding_radius(&self) -> f32 {
+        self.points
+            .iter()
+            .map(|point| point.length())
+            .fold(0.0, f32::max)
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_points(&self.points)
+    }
+
+    pub fn winding(&self) -> Winding {
+        let signed_area = self.signed_area();
+        if signed_area > 0.0 {
+            Winding::CounterClockwise
+        } else if signed_area < 0.0 {
+            Winding::Clockwise
+        } else {
+            Winding::Degenerate
+        }
+    }
+
+    // True when every corner turns the same way.
+    pub fn is_convex(&self) -> bool {
+        if !self.closed {
+            return false;
+        }
+        let count = self.points.len();
+        let mut turn_sign = 0.0;
+        for i in 0..count {
+            let a = self.points[i];
+            let b = self.points[(i + 1) % count];
+            let c = self.points[(i + 2) % count];
+            let turn = (b - a).perp_dot(c - b);
+            if turn == 0.0 {
+                continue;
+            }
+            if turn_sign == 0.0 {
+                turn_sign = turn.signum();
+            } else if turn.signum() != turn_sign {
+                return false;
+            }
+        }
+        true
+    }
+
+    // True when no two edges cross or touch, other than neighbours sharing a corner.
+    pub fn is_simple(&self) -> bool {
+        let edges: Vec<(Vec2, Vec2)> = self.edges().collect();
+        let count = edges.len();
+        for i in 0..count {
+            for j in (i + 1)..count {
+                let neighbours = j == i + 1 || (self.closed && i == 0 && j == count - 1);
+                if neighbours {
+                    continue;
+                }
+                if segments_intersect(edges[i].0, edges[i].1, edges[j].0, edges[j].1) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::collision::point_in_polygon;
+
+    fn square() -> Polygon {
+        Polygon::closed(vec![
+            vec2(0.0, 0.0),
+            vec2(2.0, 0.0),
+            vec2(2.0, 2.0),
+            vec2(0.0, 2.0),
+        ])
+        .unwrap()
+    }
+
+    // 2 x 2 with the top right 1 x 1 corner missing
+    fn l_shape() -> Polygon {
+        Polygon::closed(vec![
+            vec2(0.0, 0.0),
+            vec2(2.0, 0.0),
+            vec2(2.0, 1.0),
+            vec2(1.0, 1.0),
+            vec2(1.0, 2.0),
+            vec2(0.0, 2.0),
+        ])
+        .unwrap()
+    }
+
+    // two triangles meeting where the middle edges cross
+    fn bow_tie() -> Polygon {
+        Polygon::closed(vec![
+            vec2(0.0, 0.0),
+            vec2(2.0, 2.0),
+            vec2(2.0, 0.0),
+            vec2(0.0, 2.0),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn too_few_or_broken_points_are_rejected() {
+        assert_eq!(
+            Polygon::closed(vec![vec2(0.0, 0.0), vec2(1.0, 0.0)]).unwrap_err(),
+            PolygonError::TooFewPoints {
+                required: 3,
+                found: 2
+            }
+        );
+        assert_eq!(
+            Polygon::open(vec![vec2(0.0, 0.0)]).unwrap_err(),
+            PolygonError::TooFewPoints {
+                required: 2,
+                found: 1
+            }
+        );
+        assert_eq!(
+            Polygon::closed(vec![vec2(0.0, 0.0), vec2(1.0, f32::NAN), vec2(0.0, 1.0)]).unwrap_err(),
+            PolygonError::NonFinitePoint { index: 1 }
+        );
+    }
+
+    #[test]
+    fn repeated_closing_point_is_dropped() {
+        let triangle = Polygon::closed(vec![
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(0.0, 0.0),
+        ])
+        .unwrap();
+        assert_eq!(triangle.points().len(), 3);
+        assert_eq!(triangle.edges().count(), 3);
+        // a triangle written as three points and the repeat isn't enough
+        assert!(Polygon::closed(vec![vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(0.0, 0.0)]).is_err());
+    }
+
+    #[test]
+    fn known_shapes_have_known_properties() {
+        let square = square();
+        assert_eq!(square.signed_area(), 4.0);
+        assert_eq!(square.centroid(), vec2(1.0, 1.0));
+        assert_eq!(square.winding(), Winding::CounterClockwise);
+        assert!(square.is_convex());
+        assert!(square.is_simple());
+
+        let reversed = Polygon::closed(square.points().iter().rev().copied().collect()).unwrap();
+        assert_eq!(reversed.signed_area(), -4.0);
+        assert_eq!(reversed.winding(), Winding::Clockwise);
+
+        let l_shape = l_shape();
+        assert_eq!(l_shape.signed_area(), 3.0);
+        // three unit squares centered at (0.5, 0.5), (1.5, 0.5) and (0.5, 1.5)
+        assert!(l_shape.centroid().distance(vec2(5.0 / 6.0, 5.0 / 6.0)) < 1e-5);
+        assert!(!l_shape.is_convex());
+        assert!(l_shape.is_simple());
+
+        let bow_tie = bow_tie();
+        // the two halves wind opposite ways and cancel out
+        assert_eq!(bow_tie.signed_area(), 0.0);
+        assert_eq!(bow_tie.winding(), Winding::Degenerate);
+        assert!(!bow_tie.is_simple());
+    }
+
+    #[test]
+    fn open_polylines_have_no_inside() {
+        let streak = Polygon::open(vec![
+            vec2(0.0, 0.0),
+            vec2(2.0, 0.0),
+            vec2(2.0, 2.0),
+            vec2(0.0, 2.0),
+        ])
+        .unwrap();
+        assert_eq!(streak.signed_area(), 0.0);
+        assert!(!streak.is_convex());
+        assert!(!point_in_polygon(&vec2(1.0, 1.0), &streak));
+        assert!(point_in_polygon(&vec2(1.0, 1.0), &square()));
+    }
+}
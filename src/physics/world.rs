@@ -0,0 +1,65 @@
+use macroquad::prelude::*;
+
+// Bounds of the play area, origin in the top left corner.
+// Passed into the simulation so it never has to ask the window for its size.
+#[derive(Debug, Clone, Copy)]
+pub struct World {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl World {
+    pub fn new(width: f32, height: f32) -> World {
+        World { width, height }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        vec2(self.width / 2.0, self.height / 2.0)
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x >= 0.0 && point.x <= self.width && point.y >= 0.0 && point.y <= self.height
+    }
+
+    // Shortest displacement from `from` to `to` when the edges wrap around.
+    pub fn wrapped_delta(&self, from: Vec2, to: Vec2) -> Vec2 {
+        let mut delta = to - from;
+        if delta.x > self.width / 2.0 {
+            delta.x -= self.width;
+        } else if delta.x < -self.width / 2.0 {
+            delta.x += self.width;
+        }
+        if delta.y > self.height / 2.0 {
+            delta.y -= self.height;
+        } else if delta.y < -self.height / 2.0 {
+            delta.y += self.height;
+        }
+        delta
+    }
+
+    // Offsets of the copies of a circle that are visible when the edges wrap around:
+    // zero for the circle itself, plus one for every edge and corner it hangs over.
+    pub fn wrap_offsets(&self, center: Vec2, radius: f32) -> Vec<Vec2> {
+        let mut x_offsets = vec![0.0];
+        if center.x - radius < 0.0 {
+            x_offsets.push(self.width);
+        }
+        if center.x + radius > self.width {
+            x_offsets.push(-self.width);
+        }
+        let mut y_offsets = vec![0.0];
+        if center.y - radius < 0.0 {
+            y_offsets.push(self.height);
+        }
+        if center.y + radius > self.height {
+            y_offsets.push(-self.height);
+        }
+        let mut offsets = vec![];
+        for y in y_offsets.iter() {
+            for x in x_offsets.iter() {
+                offsets.push(vec2(*x, *y));
+            }
+        }
+        offsets
+    }
+}
@@ -0,0 +1,234 @@
+use macroquad::models::Vertex;
+use macroquad::prelude::*;
+
+// macroquad clamps any single piece of geometry above 10000 vertices or 5000
+// indices, so meshes are cut well below that
+const MESH_MAX_VERTICES: usize = 9000;
+const MESH_MAX_INDICES: usize = 4800;
+// corners sharper than this get a round join instead of a miter, which would
+// otherwise spike out up to infinitely far, cos of half the turn
+const MITER_LIMIT_COS: f32 = 0.5;
+const ROUND_SEGMENTS: usize = 12;
+
+// left and right edge of a thick line at one point along it
+type EdgePoints = (Vec2, Vec2);
+
+// Collects thick lines as triangles for a whole frame, so they are submitted
+// in a few `draw_mesh` calls instead of one `draw_line` per segment.
+// Meshes are kept between frames and only cleared, so a steady frame doesn't allocate.
+pub struct LineBatch {
+    meshes: Vec<Mesh>,
+    // meshes holding this frame's geometry, the rest are spare
+    used: usize,
+}
+
+impl Default for LineBatch {
+    fn default() -> Self {
+        LineBatch::new()
+    }
+}
+
+impl LineBatch {
+    pub fn new() -> LineBatch {
+        LineBatch {
+            meshes: vec![],
+            used: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for mesh in self.meshes[..self.used].iter_mut() {
+            mesh.vertices.clear();
+            mesh.indices.clear();
+        }
+        self.used = 0;
+    }
+
+    // Draws everything collected and starts over.
+    pub fn flush(&mut self) {
+        self.meshes[..self.used].iter().for_each(draw_mesh);
+        self.clear();
+    }
+
+    // A mesh with room for `vertices` more vertices and `indices` more indices.
+    fn reserve(&mut self, vertices: usize, indices: usize) -> &mut Mesh {
+        let full = self.used == 0 || {
+            let mesh = &self.meshes[self.used - 1];
+            mesh.vertices.len() + vertices > MESH_MAX_VERTICES
+                || mesh.indices.len() + indices > MESH_MAX_INDICES
+        };
+        if full {
+            if self.used == self.meshes.len() {
+                self.meshes.push(Mesh {
+                    vertices: Vec::with_capacity(MESH_MAX_VERTICES),
+                    indices: Vec::with_capacity(MESH_MAX_INDICES),
+                    texture: None,
+                });
+            }
+            self.used += 1;
+        }
+        &mut self.meshes[self.used - 1]
+    }
+
+    // Two triangles between the left and right edge points at each end.
+    fn quad(&mut self, start: EdgePoints, end: EdgePoints, color: Color) {
+        let mesh = self.reserve(4, 6);
+        let first = mesh.vertices.len() as u16;
+        for point in [start.0, start.1, end.1, end.0] {
+            mesh.vertices
+                .push(Vertex::new(point.x, point.y, 0.0, 0.0, 0.0, color));
+        }
+        mesh.indices
+            .extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+    }
+
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        let mesh = self.reserve(ROUND_SEGMENTS + 1, ROUND_SEGMENTS * 3);
+        let first = mesh.vertices.len() as u16;
+        mesh.vertices
+            .push(Vertex::new(center.x, center.y, 0.0, 0.0, 0.0, color));
+        for i in 0..ROUND_SEGMENTS {
+            let angle = i as f32 / ROUND_SEGMENTS as f32 * std::f32::consts::TAU;
+            let point = center + Vec2::from_angle(angle) * radius;
+            mesh.vertices
+                .push(Vertex::new(point.x, point.y, 0.0, 0.0, 0.0, color));
+        }
+        for i in 0..ROUND_SEGMENTS as u16 {
+            let next = (i + 1) % ROUND_SEGMENTS as u16;
+            mesh.indices
+                .extend([first, first + 1 + i, first + 1 + next]);
+        }
+    }
+
+    pub fn line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color) {
+        let Some(normal) = (end - start).perp().try_normalize() else {
+            return;
+        };
+        let offset = normal * thickness / 2.0;
+        self.quad(
+            (start + offset, start - offset),
+            (end + offset, end - offset),
+            color,
+        );
+    }
+
+    // Neighbouring segments meet in a mitered corner, or a round one if the
+    // corner is too sharp. Open ends are cut square like `draw_line`.
+    // `points` are placed by `transform` on the fly, nothing is copied.
+    pub fn polyline(
+        &mut self,
+        points: &[Vec2],
+        transform: &Mat3,
+        closed: bool,
+        thickness: f32,
+        color: Color,
+    ) {
+        let count = points.len();
+        if count < 2 {
+            return;
+        }
+        let half = thickness / 2.0;
+        let segment_count = if closed { count } else { count - 1 };
+        // a closed outline ends where it started, join that corner once
+        let first_join = self.join(points, transform, 0, closed, half, color);
+        let mut start = first_join.1;
+        for i in 0..segment_count {
+            let end_index = (i + 1) % count;
+            let (end, next_start) = if end_index == 0 {
+                first_join
+            } else {
+                self.join(points, transform, end_index, closed, half, color)
+            };
+            if points[i] != points[end_index] {
+                self.quad(start, end, color);
+            }
+            start = next_start;
+        }
+    }
+
+    // Edge points at vertex `i` for the segment ending there and the one
+    // starting there. Adds the round join itself when the corner is too sharp.
+    fn join(
+        &mut self,
+        points: &[Vec2],
+        transform: &Mat3,
+        i: usize,
+        closed: bool,
+        half: f32,
+        color: Color,
+    ) -> (EdgePoints, EdgePoints) {
+        let count = points.len();
+        let at = |i: usize| transform.transform_point2(points[i % count]);
+        let point = at(i);
+        let normal = |from: Vec2, to: Vec2| (to - from).perp().try_normalize();
+        let incoming = if i > 0 || closed {
+            normal(at(i + count - 1), point)
+        } else {
+            None
+        };
+        let outgoing = if i + 1 < count || closed {
+            normal(point, at(i + 1))
+        } else {
+            None
+        };
+        let square = |normal: Vec2| (point + normal * half, point - normal * half);
+        match (incoming, outgoing) {
+            (Some(incoming), Some(outgoing)) => {
+                let miter = (incoming + outgoing).normalize_or_zero();
+                let cos = miter.dot(incoming);
+                if cos > MITER_LIMIT_COS {
+                    let edges = square(miter / cos);
+                    (edges, edges)
+                } else {
+                    self.circle(point, half, color);
+                    (square(incoming), square(outgoing))
+                }
+            }
+            (Some(normal), None) | (None, Some(normal)) => (square(normal), square(normal)),
+            (None, None) => (square(Vec2::ZERO), square(Vec2::ZERO)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertices(batch: &LineBatch) -> Vec<Vec2> {
+        batch.meshes[..batch.used]
+            .iter()
+            .flat_map(|mesh| {
+                mesh.vertices
+                    .iter()
+                    .map(|vertex| vertex.position.truncate())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn right_angle_corner_is_mitered() {
+        let mut batch = LineBatch::new();
+        let points = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0)];
+        batch.polyline(&points, &Mat3::IDENTITY, false, 2.0, WHITE);
+        let vertices = vertices(&batch);
+        // a quad per segment and nothing for the join
+        assert_eq!(vertices.len(), 8);
+        // both segments end on the corner's outer and inner miter points
+        for miter in [vec2(9.0, 1.0), vec2(11.0, -1.0)] {
+            let count = vertices.iter().filter(|v| v.distance(miter) < 1e-5).count();
+            assert_eq!(count, 2, "{miter} is shared by both segments");
+        }
+    }
+
+    #[test]
+    fn hairpin_corner_is_rounded() {
+        let mut batch = LineBatch::new();
+        let points = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(0.0, 1.0)];
+        batch.polyline(&points, &Mat3::IDENTITY, false, 2.0, WHITE);
+        // the two quads plus a circle's center and rim
+        assert_eq!(vertices(&batch).len(), 8 + ROUND_SEGMENTS + 1);
+
+        batch.clear();
+        assert!(vertices(&batch).is_empty());
+    }
+}
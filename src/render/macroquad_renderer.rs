@@ -0,0 +1,82 @@
+use crate::render::line_batch::LineBatch;
+use crate::render::renderer::Renderer;
+use macroquad::prelude::*;
+
+// Draws to the window, the renderer the game runs with. Lines and circles are
+// collected into a `LineBatch` and only drawn on `flush`, or before text so
+// text still ends up on top of what was drawn before it.
+pub struct MacroquadRenderer {
+    batch: LineBatch,
+    // window pixels per world unit, text is rasterized at the window's
+    // resolution so it stays sharp however far the camera scales the world
+    pub text_scale: f32,
+}
+
+impl Default for MacroquadRenderer {
+    fn default() -> Self {
+        MacroquadRenderer::new()
+    }
+}
+
+impl MacroquadRenderer {
+    pub fn new() -> MacroquadRenderer {
+        MacroquadRenderer {
+            batch: LineBatch::new(),
+            text_scale: 1.0,
+        }
+    }
+
+    fn font_size(&self, font_size: f32) -> u16 {
+        (font_size * self.text_scale).round().max(1.0) as u16
+    }
+}
+
+impl Renderer for MacroquadRenderer {
+    fn clear(&mut self, color: Color) {
+        self.batch.clear();
+        clear_background(color);
+    }
+
+    fn line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color) {
+        self.batch.line(start, end, thickness, color);
+    }
+
+    fn polyline(
+        &mut self,
+        points: &[Vec2],
+        transform: &Mat3,
+        closed: bool,
+        thickness: f32,
+        color: Color,
+    ) {
+        self.batch
+            .polyline(points, transform, closed, thickness, color);
+    }
+
+    fn text(&mut self, text: &str, point: Vec2, font_size: f32, color: Color) {
+        self.batch.flush();
+        draw_text_ex(
+            text,
+            point.x,
+            point.y,
+            TextParams {
+                font_size: self.font_size(font_size),
+                font_scale: 1.0 / self.text_scale,
+                color,
+                ..Default::default()
+            },
+        );
+    }
+
+    fn text_width(&self, text: &str, font_size: f32) -> f32 {
+        measure_text(text, None, self.font_size(font_size), 1.0 / self.text_scale).width
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        self.batch.circle(center, radius, color);
+    }
+
+    fn flush(&mut self) {
+        self.batch.flush();
+    }
+}
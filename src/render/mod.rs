@@ -1 +1,7 @@
+pub mod line_batch;
+pub mod macroquad_renderer;
+pub mod renderer;
+pub mod scene;
 pub mod shape;
+pub mod svg_renderer;
+pub mod transform;
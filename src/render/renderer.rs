@@ -0,0 +1,46 @@
+use macroquad::prelude::*;
+
+// Everything the game draws goes through this, so a frame can be produced
+// without a window or GL context, e.g. by `SvgRenderer` for snapshots.
+// Coordinates are world pixels with the origin in the top left corner.
+pub trait Renderer {
+    fn clear(&mut self, color: Color);
+
+    fn line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color);
+
+    // `points` are placed by `transform` as they are drawn, so shapes can be moved
+    // without copying them first. `closed` also draws the edge from the last
+    // point back to the first.
+    fn polyline(
+        &mut self,
+        points: &[Vec2],
+        transform: &Mat3,
+        closed: bool,
+        thickness: f32,
+        color: Color,
+    ) {
+        let place = |point: Vec2| transform.transform_point2(point);
+        for pair in points.windows(2) {
+            self.line(place(pair[0]), place(pair[1]), thickness, color);
+        }
+        if closed && points.len() > 2 {
+            self.line(
+                place(points[points.len() - 1]),
+                place(points[0]),
+                thickness,
+                color,
+            );
+        }
+    }
+
+    // `point` is the left end of the text's baseline, like macroquad's `draw_text`.
+    fn text(&mut self, text: &str, point: Vec2, font_size: f32, color: Color);
+
+    // How wide `text` comes out, for centering and aligning it.
+    fn text_width(&self, text: &str, font_size: f32) -> f32;
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color);
+
+    // Submits anything a backend has batched up, call once the frame is drawn.
+    fn flush(&mut self) {}
+}
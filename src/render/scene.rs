@@ -0,0 +1,97 @@
+use crate::render::renderer::Renderer;
+use crate::render::shape::Shape;
+use crate::render::transform::Transform2D;
+use macroquad::prelude::*;
+
+// A shape placed relative to its parent. Moving, turning or scaling a node
+// carries all of its children along, so parts like flames or turrets are
+// defined once in their parent's space and never recomputed by hand.
+pub struct SceneNode {
+    pub local: Transform2D,
+    pub shape: Option<Shape>,
+    // hides the node and all of its children
+    pub visible: bool,
+    pub children: Vec<SceneNode>,
+}
+
+impl SceneNode {
+    pub fn new(local: Transform2D, shape: Option<Shape>) -> SceneNode {
+        SceneNode {
+            local,
+            shape,
+            visible: true,
+            children: vec![],
+        }
+    }
+
+    pub fn with_child(mut self, child: SceneNode) -> SceneNode {
+        self.children.push(child);
+        self
+    }
+
+    // Recomputes the world space points of this node and its children, see `Shape::update_world`.
+    pub fn update_world(&mut self, parent: &Mat3) {
+        let world = *parent * self.local.matrix();
+        if let Some(shape) = self.shape.as_mut() {
+            shape.update_world_matrix(&world);
+        }
+        self.children
+            .iter_mut()
+            .for_each(|child| child.update_world(&world));
+    }
+
+    // Largest distance from the root's origin any point can reach, for culling and wrapping.
+    pub fn bounding_radius(&self) -> f32 {
+        let own = self
+            .shape
+            .as_ref()
+            .map(|shape| shape.bounding_radius() * self.local.scale.abs().max_element())
+            .unwrap_or(0.0);
+        let children = self
+            .children
+            .iter()
+            .map(|child| child.bounding_radius())
+            .fold(0.0, f32::max);
+        self.local.translation.length() + own.max(children * self.local.scale.abs().max_element())
+    }
+
+    // `transform` is applied to the world space points, see `Shape::draw`.
+    pub fn draw(&self, renderer: &mut dyn Renderer, transform: &Mat3) {
+        if !self.visible {
+            return;
+        }
+        if let Some(shape) = self.shape.as_ref() {
+            shape.draw(renderer, transform);
+        }
+        self.children
+            .iter()
+            .for_each(|child| child.draw(renderer, transform));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::polygon::Polygon;
+
+    #[test]
+    fn mirrored_nodes_keep_their_bounding_radius() {
+        let streak = || {
+            Shape::new(
+                Polygon::open(vec![vec2(0.0, 0.0), vec2(4.0, 0.0)]).unwrap(),
+                WHITE,
+                1.0,
+            )
+        };
+        // flipped and doubled along x, with a flipped child one unit further out
+        let node = SceneNode::new(
+            Transform2D::IDENTITY.with_scale(vec2(-2.0, 1.0)),
+            Some(streak()),
+        )
+        .with_child(SceneNode::new(
+            Transform2D::new(vec2(1.0, 0.0), 0.0).with_scale(vec2(-1.0, -1.0)),
+            Some(streak()),
+        ));
+        assert_eq!(node.bounding_radius(), 10.0);
+    }
+}
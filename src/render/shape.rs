@@ -1,59 +1,104 @@
+use crate::physics::body::Body;
 use crate::physics::collision::is_colliding;
+use crate::physics::polygon::Polygon;
+use crate::physics::world::World;
+use crate::render::renderer::Renderer;
 use macroquad::prelude::*;
 
 pub struct Shape {
-    pub points: Vec<Vec2>,
+    pub points: Polygon,
     pub color: Color,
     pub thickness: f32,
-}
-
-fn rotate_point(base: Vec2, end: Vec2, rotation: f32) -> Vec2 {
-    // https://math.stackexchange.com/questions/270194/how-to-find-the-vertices-angle-after-rotation
-    let dx = end.x - base.x;
-    let dy = end.y - base.y;
-    let x = base.x + dx * rotation.cos() - dy * rotation.sin();
-    let y = base.y + dx * rotation.sin() + dy * rotation.cos();
-    return vec2(x, y);
+    // `points` placed in the world at the last `update_world`, shared by
+    // collision and drawing so a step transforms every shape once
+    world_points: Polygon,
 }
 
 impl Shape {
-    pub fn transform(&self, center: Vec2, rotation: f32) -> Vec<Vec2> {
-        let translated_points: Vec<Vec2> =
-            self.points.iter().map(|point| *point + center).collect();
-        let rotated_points: Vec<Vec2> = translated_points
-            .iter()
-            .map(|point| rotate_point(center, *point, rotation))
-            .collect();
-        return rotated_points;
-    }
-
-    pub fn draw(&self, center: Vec2, rotation: f32) {
-        let transformed_points = self.transform(center, rotation);
-        let mut prev_point = transformed_points.first().unwrap();
-        let remaining_points = &transformed_points[1..];
-        for point in remaining_points {
-            draw_line(
-                prev_point.x,
-                prev_point.y,
-                point.x,
-                point.y,
-                self.thickness,
-                self.color,
-            );
-            prev_point = point;
+    pub fn new(points: Polygon, color: Color, thickness: f32) -> Shape {
+        Shape {
+            world_points: points.clone(),
+            points,
+            color,
+            thickness,
+        }
+    }
+
+    // Allocates a new polygon, prefer `update_world` and `world` for anything done every step.
+    pub fn transform(&self, center: Vec2, rotation: f32) -> Polygon {
+        let mut transformed_points = self.points.clone();
+        self.points
+            .transform_into(&mut transformed_points, center, rotation);
+        transformed_points
+    }
+
+    // Recomputes the world space points, reusing the buffer from the last step.
+    pub fn update_world(&mut self, center: Vec2, rotation: f32) {
+        self.points
+            .transform_into(&mut self.world_points, center, rotation);
+    }
+
+    // Places the shape at `body`'s pose. Entities call this once per step after
+    // their body moved, so collision and `render` both see where it is now.
+    pub fn update_from_body(&mut self, body: &Body) {
+        self.update_world(body.point, body.rotation);
+    }
+
+    pub fn update_world_matrix(&mut self, matrix: &Mat3) {
+        self.points
+            .transform_matrix_into(&mut self.world_points, matrix);
+    }
+
+    pub fn world(&self) -> &Polygon {
+        &self.world_points
+    }
+
+    // Radius of the circle around the shape's origin that contains every point.
+    pub fn bounding_radius(&self) -> f32 {
+        self.points.bounding_radius()
+    }
+
+    // Draws the world space points placed by `transform`, e.g. at the
+    // interpolated pose or on the other side of a wrapped edge.
+    pub fn draw(&self, renderer: &mut dyn Renderer, transform: &Mat3) {
+        renderer.polyline(
+            self.world_points.points(),
+            transform,
+            self.world_points.is_closed(),
+            self.thickness,
+            self.color,
+        );
+    }
+
+    // Draws the cached world points at `body`'s interpolated pose, once more on
+    // the far side of every wrapped edge the shape hangs over.
+    pub fn render(&self, renderer: &mut dyn Renderer, body: &Body, alpha: f32, world: &World) {
+        let (point, _) = body.interpolated(alpha);
+        let interpolation = body.interpolation_transform(alpha);
+        for offset in body.wrap_offsets(point, self.bounding_radius(), world) {
+            self.draw(renderer, &(Mat3::from_translation(offset) * interpolation));
         }
     }
 
-    pub fn collides_with(
-        &self,
-        center: Vec2,
-        rotation: f32,
-        other: &Shape,
-        other_center: Vec2,
-        other_rotation: f32,
-    ) -> bool {
-        let transformed_points = self.transform(center, rotation);
-        let other_transformed_points = other.transform(other_center, other_rotation);
-        return is_colliding(&transformed_points, &other_transformed_points);
+    // Draws the local points placed by `matrix` rather than the cached world
+    // points, for one-off copies such as HUD icons.
+    pub fn draw_with(&self, renderer: &mut dyn Renderer, matrix: &Mat3) {
+        renderer.polyline(
+            self.points.points(),
+            matrix,
+            self.points.is_closed(),
+            self.thickness,
+            self.color,
+        );
+    }
+
+    // `offset` moves this shape, e.g. onto the copy across a wrapped edge.
+    pub fn collides_with(&self, offset: Vec2, other: &Shape) -> bool {
+        if offset == Vec2::ZERO {
+            return is_colliding(&self.world_points, &other.world_points);
+        }
+        // only near wrapped edges
+        let moved_points = self.world_points.map(|point| point + offset);
+        is_colliding(&moved_points, &other.world_points)
     }
 }
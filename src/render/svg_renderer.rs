@@ -0,0 +1,133 @@
+use crate::render::renderer::Renderer;
+use macroquad::prelude::*;
+use std::fmt::Write;
+
+// Collects a frame as an SVG document instead of drawing it, so frames can be
+// exported or compared against golden files without a window.
+// Numbers are written with two decimals so the same frame always gives the same text.
+pub struct SvgRenderer {
+    pub width: f32,
+    pub height: f32,
+    background: Color,
+    elements: String,
+}
+
+impl SvgRenderer {
+    pub fn new(width: f32, height: f32) -> SvgRenderer {
+        SvgRenderer {
+            width,
+            height,
+            background: BLACK,
+            elements: String::new(),
+        }
+    }
+
+    pub fn to_svg(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.2}\" height=\"{:.2}\" viewBox=\"0 0 {:.2} {:.2}\">\n\
+             <rect width=\"100%\" height=\"100%\" {}/>\n{}</svg>\n",
+            self.width,
+            self.height,
+            self.width,
+            self.height,
+            paint("fill", self.background),
+            self.elements
+        )
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_svg())
+    }
+}
+
+// `fill="rgb(..)"` plus an opacity attribute if the color is see-through.
+fn paint(attribute: &str, color: Color) -> String {
+    let [r, g, b, a]: [u8; 4] = color.into();
+    let mut paint = format!("{}=\"rgb({},{},{})\"", attribute, r, g, b);
+    if a < 255 {
+        let _ = write!(paint, " {}-opacity=\"{:.2}\"", attribute, color.a);
+    }
+    paint
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Renderer for SvgRenderer {
+    // Drops everything drawn so far, like clearing the screen.
+    fn clear(&mut self, color: Color) {
+        self.background = color;
+        self.elements.clear();
+    }
+
+    fn line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color) {
+        let _ = writeln!(
+            self.elements,
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke-width=\"{:.2}\" {}/>",
+            start.x,
+            start.y,
+            end.x,
+            end.y,
+            thickness,
+            paint("stroke", color)
+        );
+    }
+
+    fn polyline(
+        &mut self,
+        points: &[Vec2],
+        transform: &Mat3,
+        closed: bool,
+        thickness: f32,
+        color: Color,
+    ) {
+        let element = if closed { "polygon" } else { "polyline" };
+        let mut coordinates = String::new();
+        for point in points
+            .iter()
+            .map(|point| transform.transform_point2(*point))
+        {
+            let _ = write!(coordinates, "{:.2},{:.2} ", point.x, point.y);
+        }
+        let _ = writeln!(
+            self.elements,
+            "<{} points=\"{}\" fill=\"none\" stroke-width=\"{:.2}\" {}/>",
+            element,
+            coordinates.trim_end(),
+            thickness,
+            paint("stroke", color)
+        );
+    }
+
+    fn text(&mut self, text: &str, point: Vec2, font_size: f32, color: Color) {
+        let _ = writeln!(
+            self.elements,
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"monospace\" font-size=\"{:.2}\" {}>{}</text>",
+            point.x,
+            point.y,
+            font_size,
+            paint("fill", color),
+            escape(text)
+        );
+    }
+
+    // Glyphs of a monospace font are about 0.6 em wide, close enough for layout.
+    fn text_width(&self, text: &str, font_size: f32) -> f32 {
+        text.chars().count() as f32 * font_size * 0.6
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        let _ = writeln!(
+            self.elements,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" {}/>",
+            center.x,
+            center.y,
+            radius,
+            paint("fill", color)
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use macroquad::prelude::*;
+
+// Translation, rotation and non-uniform scale, applied in the order
+// scale -> rotate -> translate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2D {
+    pub translation: Vec2,
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+impl Transform2D {
+    pub const IDENTITY: Transform2D = Transform2D {
+        translation: Vec2::ZERO,
+        rotation: 0.0,
+        scale: Vec2::ONE,
+    };
+
+    pub fn new(translation: Vec2, rotation: f32) -> Transform2D {
+        Transform2D {
+            translation,
+            rotation,
+            scale: Vec2::ONE,
+        }
+    }
+
+    pub fn with_scale(self, scale: Vec2) -> Transform2D {
+        Transform2D { scale, ..self }
+    }
+
+    // Homogeneous 3x3 matrix, multiply parent * child to nest transforms.
+    // https://en.wikipedia.org/wiki/Transformation_matrix#Affine_transformations
+    pub fn matrix(&self) -> Mat3 {
+        Mat3::from_scale_angle_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+impl Default for Transform2D {
+    fn default() -> Transform2D {
+        Transform2D::IDENTITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_scales_then_rotates_then_translates() {
+        let transform = Transform2D::new(vec2(10.0, 0.0), std::f32::consts::FRAC_PI_2)
+            .with_scale(vec2(2.0, 1.0));
+        let point = transform.matrix().transform_point2(vec2(1.0, 1.0));
+        // (2, 1) after scaling, (-1, 2) after the quarter turn
+        assert!(point.distance(vec2(9.0, 2.0)) < 1e-5);
+    }
+}
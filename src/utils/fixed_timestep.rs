@@ -0,0 +1,38 @@
+// Accumulator that turns variable frame times into a whole number of fixed
+// simulation steps, plus the leftover fraction used to interpolate rendering.
+// https://gafferongames.com/post/fix_your_timestep/
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    pub step: f32,
+    max_frame_time: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(steps_per_second: f32) -> FixedTimestep {
+        FixedTimestep {
+            step: 1.0 / steps_per_second,
+            // avoid the spiral of death after a long stall (window drag, breakpoint...)
+            max_frame_time: 0.25,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn advance(&mut self, frame_time: f32) {
+        self.accumulator += frame_time.clamp(0.0, self.max_frame_time);
+    }
+
+    // Returns true while there is enough accumulated time for another step.
+    pub fn consume_step(&mut self) -> bool {
+        if self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            return true;
+        }
+        false
+    }
+
+    // How far between the previous and current simulation state the frame is, 0..1.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+}
@@ -0,0 +1,40 @@
+use macroquad::rand::RandGenerator;
+
+// Random streams owned by the game instead of macroquad's global generator.
+// Gameplay decisions and cosmetic effects draw from separate streams so that
+// changing a visual effect never changes how a seeded game plays out.
+pub struct GameRng {
+    pub seed: u64,
+    pub gameplay: RandGenerator,
+    pub cosmetic: RandGenerator,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> GameRng {
+        let gameplay = RandGenerator::new();
+        gameplay.srand(mix_seed(seed, 0));
+        let cosmetic = RandGenerator::new();
+        cosmetic.srand(mix_seed(seed, 1));
+        GameRng {
+            seed,
+            gameplay,
+            cosmetic,
+        }
+    }
+
+    // Streams for one level, so a level plays the same however the previous one went.
+    pub fn for_level(seed: u64, level: u32) -> GameRng {
+        GameRng::new(mix_seed(seed, level as u64 + 2))
+    }
+}
+
+// splitmix64, spreads nearby seeds far apart
+// https://prng.di.unimi.it/splitmix64.c
+pub fn mix_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed
+        .wrapping_add(stream.wrapping_mul(0x9E3779B97F4A7C15))
+        .wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
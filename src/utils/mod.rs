@@ -1 +1,3 @@
+pub mod fixed_timestep;
+pub mod game_rng;
 pub mod screen_util;
@@ -1,9 +1,50 @@
+use crate::physics::world::World;
 use macroquad::prelude::*;
 
-pub fn get_center_x() -> f32 {
-    return (screen_width() / 2.0) - 60.0;
+// Part of the window the world is drawn into, in window pixels from the top left.
+// The world keeps its aspect ratio, the rest of the window is left as bars.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
 }
 
-pub fn get_center_y() -> f32 {
-    return screen_height() / 2.0;
+impl Viewport {
+    // Largest viewport with the world's aspect ratio that fits the window,
+    // bars above and below for a wide world (letterbox), left and right for a narrow one (pillarbox).
+    pub fn letterbox(world: &World, window_width: f32, window_height: f32) -> Viewport {
+        let scale = (window_width / world.width).min(window_height / world.height);
+        let width = world.width * scale;
+        let height = world.height * scale;
+        Viewport {
+            x: ((window_width - width) / 2.0).floor(),
+            y: ((window_height - height) / 2.0).floor(),
+            width,
+            height,
+        }
+    }
+
+    // Window pixels per world unit.
+    pub fn scale(&self, world: &World) -> f32 {
+        self.width / world.width
+    }
+
+    // Maps the whole world onto this viewport with y pointing down, like window pixels.
+    pub fn camera(&self, world: &World) -> Camera2D {
+        Camera2D {
+            target: world.center(),
+            zoom: vec2(2.0 / world.width, 2.0 / world.height),
+            // bars are the same size on both sides, so it doesn't matter that
+            // GL counts y from the bottom
+            viewport: Some((
+                self.x as i32,
+                self.y as i32,
+                self.width as i32,
+                self.height as i32,
+            )),
+            ..Camera2D::default()
+        }
+    }
 }
@@ -0,0 +1,159 @@
+use asteroids::game::components::asteroid::{Asteroid, AsteroidSizes};
+use asteroids::game::components::bullet::Bullet;
+use asteroids::game::core::game_config::GameConfig;
+use asteroids::game::core::game_input::InputFrame;
+use asteroids::game::core::game_render::render;
+use asteroids::game::core::game_state_machine::update_game_state;
+use asteroids::game::core::game_states::GameState;
+use asteroids::physics::body::ScreenEdgeBehavior;
+use asteroids::physics::polygon::Polygon;
+use asteroids::physics::world::World;
+use asteroids::render::svg_renderer::SvgRenderer;
+use macroquad::prelude::*;
+
+const DT: f32 = 1.0 / 120.0;
+const STEPS: usize = 600;
+const SNAPSHOT: &str = "tests/snapshots/seed_42_step_600.svg";
+
+// The same made up player every run: starts a game, then turns, thrusts,
+// shoots and jumps to hyperspace on a fixed schedule.
+fn scripted_input(step: usize) -> InputFrame {
+    InputFrame {
+        confirm: step == 0,
+        rotate_left: step % 240 < 60,
+        thrust_forward: (100..200).contains(&step),
+        fire: step.is_multiple_of(30),
+        fire_held: step % 120 < 40,
+        hyperspace: step == 400,
+        ..Default::default()
+    }
+}
+
+fn play(seed: u64) -> (GameState, GameConfig, World) {
+    let world = World::new(800.0, 600.0);
+    let mut config = GameConfig::new(seed);
+    let mut game_state = GameState::MainMenu;
+    for step in 0..STEPS {
+        update_game_state(
+            &mut game_state,
+            &mut config,
+            &scripted_input(step),
+            DT,
+            &world,
+        );
+    }
+    (game_state, config, world)
+}
+
+fn frame(game_state: &GameState, config: &GameConfig, world: &World) -> String {
+    let mut renderer = SvgRenderer::new(world.width, world.height);
+    render(&mut renderer, game_state, config, 1.0, world);
+    renderer.to_svg()
+}
+
+#[test]
+fn same_seed_and_inputs_give_the_same_game() {
+    let (first, config, world) = play(42);
+    let (second, _, _) = play(42);
+    let GameState::Playing { playing_info } = &first else {
+        panic!("the scripted game should still be running");
+    };
+    let GameState::Playing {
+        playing_info: replayed,
+    } = &second
+    else {
+        panic!("the replayed game should still be running");
+    };
+    assert!(playing_info.score > 0, "the script should hit something");
+    assert_eq!(playing_info.score, replayed.score);
+    assert_eq!(playing_info.lives, replayed.lives);
+    assert_eq!(playing_info.asteroids.len(), replayed.asteroids.len());
+    for (a, b) in playing_info.asteroids.iter().zip(replayed.asteroids.iter()) {
+        assert_eq!(a.body.point, b.body.point);
+        assert_eq!(a.body.velocity, b.body.velocity);
+        assert_eq!(a.shape.points.points(), b.shape.points.points());
+    }
+    assert_eq!(
+        playing_info.space_ship.body.point,
+        replayed.space_ship.body.point
+    );
+    // everything else that shows, particles included
+    assert_eq!(
+        frame(&first, &config, &world),
+        frame(&second, &config, &world)
+    );
+
+    let (other_seed, config, world) = play(43);
+    assert_ne!(
+        frame(&first, &config, &world),
+        frame(&other_seed, &config, &world)
+    );
+}
+
+// Compares the last frame of the scripted game against the saved SVG.
+// After an intended change to how the game looks or plays, rerun with
+// `UPDATE_SNAPSHOTS=1 cargo test` and check the new file.
+#[test]
+fn scripted_game_matches_snapshot() {
+    let (game_state, config, world) = play(42);
+    let svg = frame(&game_state, &config, &world);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(SNAPSHOT, &svg).expect("snapshot is writable");
+    }
+    let expected = std::fs::read_to_string(SNAPSHOT)
+        .expect("snapshot exists, create it with UPDATE_SNAPSHOTS=1 cargo test");
+    assert!(
+        svg == expected,
+        "frame differs from {SNAPSHOT}, rerun with UPDATE_SNAPSHOTS=1 if that's intended"
+    );
+}
+
+#[test]
+fn bullet_hits_the_copy_of_an_asteroid_across_the_edge() {
+    let world = World::new(800.0, 600.0);
+    let mut config = GameConfig::new(42);
+    let mut game_state = GameState::MainMenu;
+    let start = InputFrame {
+        confirm: true,
+        ..Default::default()
+    };
+    update_game_state(&mut game_state, &mut config, &start, DT, &world);
+    let GameState::Playing { playing_info } = &mut game_state else {
+        panic!("confirming in the menu should start a game");
+    };
+
+    // hangs off the top, its copy shows at the bottom between y 545 and 600
+    let outline = Polygon::closed(vec![
+        vec2(-60.0, -60.0),
+        vec2(60.0, -60.0),
+        vec2(60.0, 60.0),
+        vec2(-60.0, 60.0),
+    ])
+    .unwrap();
+    let mut asteroid = Asteroid::new(vec2(400.0, 5.0), 0.0, 0.0, &AsteroidSizes::Large, outline);
+    asteroid.body.velocity = Vec2::ZERO;
+    playing_info.asteroids = vec![asteroid];
+    // flies along the bottom through the copy, never near the asteroid itself
+    playing_info.bullets = vec![Bullet::new(
+        vec2(300.0, 550.0),
+        0.0,
+        500.0,
+        ScreenEdgeBehavior::Wrap,
+    )];
+
+    for _ in 0..30 {
+        update_game_state(
+            &mut game_state,
+            &mut config,
+            &InputFrame::default(),
+            DT,
+            &world,
+        );
+    }
+    let score = match &game_state {
+        GameState::Playing { playing_info } => playing_info.score,
+        GameState::NextLevel { score, .. } => *score,
+        _ => panic!("the game should still be running"),
+    };
+    assert!(score > 0, "the bullet should break the asteroid's copy");
+}